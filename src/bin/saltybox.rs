@@ -0,0 +1,886 @@
+//! Saltybox CLI - Passphrase-based file encryption
+//!
+//! Command-line interface for encrypting and decrypting files using
+//! NaCl secretbox (XSalsa20Poly1305) with scrypt key derivation.
+//!
+//! Either `-i`/`-o` path may be `-` to mean stdin/stdout, e.g.
+//! `tar c dir | saltybox encrypt -i - -o backup.salty` or
+//! `saltybox decrypt -i backup.salty -o - | tar x`. Note that `-i -`
+//! cannot be combined with `--passphrase-stdin`, since the passphrase
+//! reader consumes all of stdin.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process;
+
+use crypto_box::{PublicKey, SecretKey};
+use saltybox::file_ops::{self, SourceCleanup};
+use saltybox::passgen;
+use saltybox::pkcrypt;
+use saltybox::progress::{self, Progress};
+use saltybox::passphrase::{
+    CommandPassphraseReader, ConfirmingPassphraseReader, ConstantPassphraseReader,
+    DEFAULT_MIN_PASSPHRASE_BITS, EnforcingPassphraseReader, EnvVarPassphraseReader,
+    EnvelopePassphraseReader, NormalizationPolicy, NormalizingPassphraseReader, PassphraseReader,
+    ReaderPassphraseReader, TerminalPassphraseReader,
+};
+use saltybox::profile;
+use saltybox::secretcrypt::{Kdf, ScryptParams};
+
+/// Default length for a generated character-class passphrase.
+const DEFAULT_GENERATE_LENGTH: u8 = 20;
+/// Default number of words for a generated diceware passphrase.
+const DEFAULT_GENERATE_WORDS: u8 = 6;
+
+#[derive(Parser)]
+#[command(name = "saltybox")]
+#[command(version)]
+#[command(about = "Passphrase-based file encryption.", long_about = None)]
+struct Cli {
+    /// Read passphrase from stdin instead of from terminal
+    #[arg(long, global = true)]
+    passphrase_stdin: bool,
+
+    /// External askpass/pinentry-style program to invoke for the
+    /// passphrase instead of reading the controlling terminal. The program
+    /// is expected to print the passphrase to stdout. Can also be set via
+    /// the `SALTYBOX_ASKPASS` environment variable.
+    #[arg(long, global = true, value_name = "PROGRAM")]
+    askpass: Option<PathBuf>,
+
+    /// Read the passphrase from a file's raw bytes instead of the terminal
+    #[arg(long, global = true, value_name = "FILE")]
+    passphrase_file: Option<PathBuf>,
+
+    /// Read the passphrase from an environment variable's raw bytes
+    /// instead of the terminal
+    #[arg(long, global = true, value_name = "VAR")]
+    passphrase_env: Option<String>,
+
+    /// Comma-separated passphrase normalization to apply before deriving
+    /// keys: nfc, trim, sevenbit. Changing this between encrypt and
+    /// decrypt changes the derived key.
+    #[arg(long, global = true, value_name = "OPTS")]
+    normalize: Option<String>,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// Key-derivation function choices exposed on the `--kdf` flag.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum KdfChoice {
+    Scrypt,
+    Argon2id,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Encrypt a file
+    #[command(alias = "e")]
+    Encrypt {
+        /// Path to the file whose contents is to be encrypted, or `-` for stdin
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// Path to the file to write the encrypted text to, or `-` for stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Compress the plaintext with zstd before sealing
+        #[arg(long, conflicts_with_all = ["stream", "kdf", "scrypt_n", "scrypt_r", "scrypt_p"])]
+        compress: bool,
+
+        /// Armor the output with the PGP-style, line-wrapped `saltybox2:`
+        /// format instead of the compact default, so it survives being
+        /// pasted into an email or wiki page that hard-wraps lines; a
+        /// trailing CRC-24 checksum catches transcription errors before
+        /// decryption is attempted. `decrypt` auto-detects this format, no
+        /// flag needed there. Incompatible with --compress, --stream,
+        /// --kdf, --scrypt-*, --recipient-passphrase, --profile, and
+        /// --recipient-key, which all select a different format.
+        #[arg(
+            long,
+            conflicts_with_all = ["compress", "stream", "kdf", "scrypt_n", "scrypt_r", "scrypt_p", "recipient_passphrase", "profile", "recipient_key"]
+        )]
+        armor2: bool,
+
+        /// Encrypt using the chunked STREAM construction, so neither the
+        /// plaintext nor the ciphertext is ever fully buffered in memory.
+        /// Recommended for multi-gigabyte inputs; incompatible with
+        /// --compress, the --scrypt-* flags, and source cleanup.
+        #[arg(long, conflicts_with_all = ["compress", "scrypt_n", "scrypt_r", "scrypt_p", "remove_source", "shred"])]
+        stream: bool,
+
+        /// scrypt CPU/memory cost parameter N; must be a power of two.
+        /// Passing any of --scrypt-n/--scrypt-r/--scrypt-p switches to the
+        /// self-describing tunable-cost format (incompatible with --compress)
+        #[arg(long, value_name = "N", conflicts_with = "kdf")]
+        scrypt_n: Option<u32>,
+
+        /// scrypt block size parameter r
+        #[arg(long, value_name = "R", conflicts_with = "kdf")]
+        scrypt_r: Option<u8>,
+
+        /// scrypt parallelization parameter p
+        #[arg(long, value_name = "P", conflicts_with = "kdf")]
+        scrypt_p: Option<u8>,
+
+        /// Allow --scrypt-n/-r/-p to exceed the conservative default memory
+        /// ceiling, for callers who deliberately want a costlier-than-usual
+        /// key derivation and understand the memory it will consume.
+        #[arg(long)]
+        allow_expensive_scrypt: bool,
+
+        /// Key-derivation function to use, written into a self-describing
+        /// header so decryption always picks the matching routine back out.
+        /// Defaults to scrypt; pass `argon2id` for the memory-hard OWASP-
+        /// recommended alternative. Incompatible with the --scrypt-* flags,
+        /// which select a different self-describing scrypt-only format.
+        #[arg(long, value_name = "KDF", conflicts_with_all = ["scrypt_n", "scrypt_r", "scrypt_p"])]
+        kdf: Option<KdfChoice>,
+
+        /// Argon2id memory cost in KiB, only used with --kdf argon2id
+        #[arg(long, value_name = "KIB", requires = "kdf")]
+        argon2_m_cost: Option<u32>,
+
+        /// Argon2id time cost (iterations), only used with --kdf argon2id
+        #[arg(long, value_name = "N", requires = "kdf")]
+        argon2_t_cost: Option<u32>,
+
+        /// Argon2id parallelism, only used with --kdf argon2id
+        #[arg(long, value_name = "P", requires = "kdf")]
+        argon2_p_cost: Option<u32>,
+
+        /// Allow a passphrase whose estimated entropy falls below
+        /// --min-passphrase-bits, for callers who know their passphrase is
+        /// already high-entropy (e.g. machine-generated) despite looking
+        /// weak to the heuristic.
+        #[arg(long)]
+        allow_weak_passphrase: bool,
+
+        /// Minimum estimated passphrase entropy, in bits, required unless
+        /// --allow-weak-passphrase is set
+        #[arg(long, value_name = "BITS")]
+        min_passphrase_bits: Option<f64>,
+
+        /// Delete the plaintext input file once the encrypted output is
+        /// durably written. Only supported for the default format, so
+        /// incompatible with --armor2, --kdf, --scrypt-*,
+        /// --recipient-passphrase, --profile, and --recipient-key.
+        #[arg(
+            long,
+            conflicts_with_all = ["shred", "armor2", "kdf", "scrypt_n", "scrypt_r", "scrypt_p", "recipient_passphrase", "profile", "recipient_key"]
+        )]
+        remove_source: bool,
+
+        /// Like --remove-source, but first overwrite the plaintext input
+        /// file with zeroes and fsync before deleting it. Incompatible with
+        /// the same flags as --remove-source.
+        #[arg(
+            long,
+            conflicts_with_all = ["armor2", "kdf", "scrypt_n", "scrypt_r", "scrypt_p", "recipient_passphrase", "profile", "recipient_key"]
+        )]
+        shred: bool,
+
+        /// Encrypt to multiple recipients instead of a single passphrase:
+        /// each occurrence names a file holding one recipient's passphrase
+        /// (raw bytes, same format as --passphrase-file), and any one of
+        /// them can later decrypt the file. Recipients can be added or
+        /// removed afterward with `add-recipient`/`remove-recipient`
+        /// without re-encrypting. Incompatible with --compress, --stream,
+        /// --kdf, and the --scrypt-* flags, which all select a different
+        /// self-describing format.
+        #[arg(long = "recipient-passphrase", value_name = "FILE", conflicts_with_all = ["compress", "stream", "kdf", "scrypt_n", "scrypt_r", "scrypt_p", "profile"])]
+        recipient_passphrase: Vec<PathBuf>,
+
+        /// Use the KDF and cost parameters saved by `saltybox init <NAME>`
+        /// instead of choosing them per file, and verify the passphrase
+        /// against the saved profile before encrypting. Incompatible with
+        /// --compress, --stream, --kdf, --scrypt-*, and
+        /// --recipient-passphrase, which all select a different
+        /// self-describing format.
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["compress", "stream", "kdf", "scrypt_n", "scrypt_r", "scrypt_p"])]
+        profile: Option<String>,
+
+        /// Encrypt to an SSH public key instead of a passphrase: no
+        /// passphrase is read, and only the holder of the matching secret
+        /// key can decrypt (see `decrypt --identity`). Only ed25519
+        /// OpenSSH keys are currently supported. Incompatible with
+        /// --compress, --stream, --kdf, --scrypt-*, --recipient-passphrase,
+        /// and --profile, which all select a different self-describing
+        /// format.
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["compress", "stream", "kdf", "scrypt_n", "scrypt_r", "scrypt_p", "recipient_passphrase", "profile"]
+        )]
+        recipient_key: Option<PathBuf>,
+    },
+
+    /// Decrypt a file
+    #[command(alias = "d")]
+    Decrypt {
+        /// Path to the file whose contents is to be decrypted, or `-` for stdin
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// Path to the file to write the unencrypted text to, or `-` for stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Verify the passphrase against the profile saved by `saltybox
+        /// init <NAME>` before decrypting, for a faster and clearer
+        /// "wrong passphrase" error.
+        #[arg(long, value_name = "NAME", conflicts_with = "identity")]
+        profile: Option<String>,
+
+        /// Decrypt a file produced by `encrypt --recipient-key`, using an
+        /// SSH private key file instead of a passphrase. Only ed25519
+        /// OpenSSH keys are currently supported.
+        #[arg(long, value_name = "FILE")]
+        identity: Option<PathBuf>,
+
+        /// File holding the raw passphrase that decrypts --identity's key,
+        /// if the key is itself passphrase-protected.
+        #[arg(long, value_name = "FILE", requires = "identity")]
+        identity_passphrase_file: Option<PathBuf>,
+    },
+
+    /// Update an encrypted file with new content, while validating
+    /// that the passphrase is not accidentally changed.
+    #[command(alias = "u")]
+    Update {
+        /// Path to the file whose contents is to be encrypted, or `-` for stdin
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// Path to the existing saltybox file to replace with encrypted text
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Verify the passphrase against the profile saved by `saltybox
+        /// init <NAME>` before updating, for a faster and clearer
+        /// "wrong passphrase" error.
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+    },
+
+    /// Derive KDF parameters from a passphrase once and save them, along
+    /// with a verifier (not the passphrase or derived key), to a reusable
+    /// profile. Pass `--profile <NAME>` to `encrypt`/`decrypt`/`update` to
+    /// use it.
+    Init {
+        /// Name of the profile to create; stored as `<NAME>.profile` under
+        /// the saltybox config directory (`$XDG_CONFIG_HOME/saltybox` or
+        /// `$HOME/.config/saltybox` by default; see
+        /// `SALTYBOX_CONFIG_DIR` to override).
+        name: String,
+
+        /// Key-derivation function the profile should use. Defaults to
+        /// scrypt; pass `argon2id` for the memory-hard OWASP-recommended
+        /// alternative.
+        #[arg(long, value_name = "KDF")]
+        kdf: Option<KdfChoice>,
+
+        /// Argon2id memory cost in KiB, only used with --kdf argon2id
+        #[arg(long, value_name = "KIB", requires = "kdf")]
+        argon2_m_cost: Option<u32>,
+
+        /// Argon2id time cost (iterations), only used with --kdf argon2id
+        #[arg(long, value_name = "N", requires = "kdf")]
+        argon2_t_cost: Option<u32>,
+
+        /// Argon2id parallelism, only used with --kdf argon2id
+        #[arg(long, value_name = "P", requires = "kdf")]
+        argon2_p_cost: Option<u32>,
+
+        /// Allow a passphrase whose estimated entropy falls below
+        /// --min-passphrase-bits, for callers who know their passphrase is
+        /// already high-entropy (e.g. machine-generated) despite looking
+        /// weak to the heuristic.
+        #[arg(long)]
+        allow_weak_passphrase: bool,
+
+        /// Minimum estimated passphrase entropy, in bits, required unless
+        /// --allow-weak-passphrase is set
+        #[arg(long, value_name = "BITS")]
+        min_passphrase_bits: Option<f64>,
+    },
+
+    /// Add a recipient to a multi-recipient encrypted file (see `encrypt
+    /// --recipient-passphrase`) without re-encrypting its plaintext.
+    AddRecipient {
+        /// Path to the saltybox-multi1 file to modify
+        #[arg(short, long, value_name = "FILE")]
+        file: PathBuf,
+
+        /// File holding an existing recipient's passphrase (raw bytes)
+        #[arg(long, value_name = "FILE")]
+        existing_recipient_passphrase_file: PathBuf,
+
+        /// File holding the new recipient's passphrase (raw bytes)
+        #[arg(long, value_name = "FILE")]
+        new_recipient_passphrase_file: PathBuf,
+    },
+
+    /// Remove a recipient from a multi-recipient encrypted file (see
+    /// `encrypt --recipient-passphrase`) without re-encrypting its
+    /// plaintext. Refuses to remove the last remaining recipient.
+    ///
+    /// Requires the departing recipient's own passphrase to locate their
+    /// wrap - this cannot revoke a recipient who won't supply it (a lost
+    /// device, a departing employee, a compromised credential). To force
+    /// out an uncooperative recipient, re-encrypt the plaintext to a new
+    /// file with `encrypt --recipient-passphrase` naming only the
+    /// recipients who should keep access.
+    RemoveRecipient {
+        /// Path to the saltybox-multi1 file to modify
+        #[arg(short, long, value_name = "FILE")]
+        file: PathBuf,
+
+        /// File holding the passphrase of the recipient to remove (raw bytes)
+        #[arg(long, value_name = "FILE")]
+        recipient_passphrase_file: PathBuf,
+    },
+
+    /// Generate a strong passphrase and print it to stdout
+    #[command(alias = "g")]
+    Generate {
+        /// Number of diceware words to generate; requires --dice
+        #[arg(long, value_name = "N", conflicts_with = "length", requires = "dice")]
+        words: Option<u8>,
+
+        /// Length in characters of a random character-class passphrase
+        #[arg(long, value_name = "N", conflicts_with = "dice")]
+        length: Option<u8>,
+
+        /// Path to a newline-delimited wordlist; switches to diceware mode
+        #[arg(long, value_name = "FILE")]
+        dice: Option<PathBuf>,
+    },
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let askpass = cli.askpass.clone();
+    let passphrase_file = cli.passphrase_file.clone();
+    let passphrase_env = cli.passphrase_env.clone();
+    let normalize = match cli.normalize.as_deref() {
+        Some(spec) => match saltybox::passphrase::parse_normalization_policy(spec) {
+            Ok(policy) => policy,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                process::exit(1);
+            }
+        },
+        None => NormalizationPolicy::NONE,
+    };
+
+    let result = match cli.command {
+        Commands::Encrypt {
+            input,
+            output,
+            compress,
+            armor2,
+            stream,
+            scrypt_n,
+            scrypt_r,
+            scrypt_p,
+            allow_expensive_scrypt,
+            kdf,
+            argon2_m_cost,
+            argon2_t_cost,
+            argon2_p_cost,
+            allow_weak_passphrase,
+            min_passphrase_bits,
+            remove_source,
+            shred,
+            recipient_passphrase,
+            profile,
+            recipient_key,
+        } => {
+            if let Some(key_path) = recipient_key {
+                let recipient: PublicKey = match pkcrypt::load_ssh_public_key(&key_path) {
+                    Ok(recipient) => recipient,
+                    Err(e) => {
+                        eprintln!("Error: {:#}", e);
+                        process::exit(1);
+                    }
+                };
+                if let Err(e) = file_ops::encrypt_file_with_key(&input, &output, &recipient) {
+                    eprintln!("Error: {:#}", e);
+                    process::exit(1);
+                }
+                return;
+            }
+
+            if !recipient_passphrase.is_empty() {
+                let mut readers: Vec<Box<dyn PassphraseReader>> = recipient_passphrase
+                    .into_iter()
+                    .map(|path| Box::new(EnvelopePassphraseReader::new(path)) as Box<dyn PassphraseReader>)
+                    .collect();
+                if let Err(e) = file_ops::encrypt_file_multi(&input, &output, &mut readers) {
+                    eprintln!("Error: {:#}", e);
+                    process::exit(1);
+                }
+                return;
+            }
+
+            if let Some(name) = profile {
+                let mut reader = match get_passphrase_reader(
+                    cli.passphrase_stdin,
+                    false,
+                    askpass.clone(),
+                    passphrase_file.clone(),
+                    passphrase_env.clone(),
+                    normalize,
+                    None,
+                ) {
+                    Ok(reader) => reader,
+                    Err(message) => {
+                        eprintln!("Error: {}", message);
+                        process::exit(1);
+                    }
+                };
+                if let Err(e) = encrypt_with_profile(&input, &output, &name, &mut *reader) {
+                    eprintln!("Error: {:#}", e);
+                    process::exit(1);
+                }
+                return;
+            }
+
+            let min_entropy_bits = if allow_weak_passphrase {
+                None
+            } else {
+                Some(min_passphrase_bits.unwrap_or(DEFAULT_MIN_PASSPHRASE_BITS))
+            };
+            let mut reader = match get_passphrase_reader(
+                cli.passphrase_stdin,
+                true,
+                askpass.clone(),
+                passphrase_file.clone(),
+                passphrase_env.clone(),
+                normalize,
+                min_entropy_bits,
+            ) {
+                Ok(reader) => reader,
+                Err(message) => {
+                    eprintln!("Error: {}", message);
+                    process::exit(1);
+                }
+            };
+            let cleanup = if shred {
+                SourceCleanup::Shred
+            } else if remove_source {
+                SourceCleanup::Remove
+            } else {
+                SourceCleanup::Keep
+            };
+            if armor2 {
+                file_ops::encrypt_file_armor2(&input, &output, &mut *reader)
+            } else if stream {
+                file_ops::encrypt_file_stream(
+                    &input,
+                    &output,
+                    &mut *reader,
+                    &mut *make_progress(),
+                )
+            } else if let Some(choice) = kdf {
+                match kdf_from_flags(choice, argon2_m_cost, argon2_t_cost, argon2_p_cost) {
+                    Ok(kdf) => file_ops::encrypt_file_with_kdf(&input, &output, &mut *reader, kdf),
+                    Err(message) => {
+                        eprintln!("Error: {}", message);
+                        process::exit(1);
+                    }
+                }
+            } else if scrypt_n.is_some() || scrypt_r.is_some() || scrypt_p.is_some() {
+                match scrypt_params_from_flags(scrypt_n, scrypt_r, scrypt_p) {
+                    Ok(params) => file_ops::encrypt_file_with_params(
+                        &input,
+                        &output,
+                        &mut *reader,
+                        params,
+                        allow_expensive_scrypt,
+                    ),
+                    Err(message) => {
+                        eprintln!("Error: {}", message);
+                        process::exit(1);
+                    }
+                }
+            } else {
+                file_ops::encrypt_file(&input, &output, &mut *reader, compress, cleanup)
+            }
+        }
+        Commands::Decrypt {
+            input,
+            output,
+            profile,
+            identity,
+            identity_passphrase_file,
+        } => {
+            if let Some(identity_path) = identity {
+                let identity_passphrase = match identity_passphrase_file {
+                    Some(path) => match std::fs::read(&path) {
+                        Ok(bytes) => Some(bytes),
+                        Err(e) => {
+                            eprintln!("Error: failed to read {}: {}", path.display(), e);
+                            process::exit(1);
+                        }
+                    },
+                    None => None,
+                };
+                let secret: SecretKey =
+                    match pkcrypt::load_ssh_secret_key(&identity_path, identity_passphrase.as_deref()) {
+                        Ok(secret) => secret,
+                        Err(e) => {
+                            eprintln!("Error: {:#}", e);
+                            process::exit(1);
+                        }
+                    };
+                if let Err(e) = file_ops::decrypt_file_with_key(&input, &output, &secret) {
+                    eprintln!("Error: {:#}", e);
+                    process::exit(1);
+                }
+                return;
+            }
+
+            let reader = match get_passphrase_reader(
+                cli.passphrase_stdin,
+                false,
+                askpass,
+                passphrase_file,
+                passphrase_env,
+                normalize,
+                None,
+            ) {
+                Ok(reader) => reader,
+                Err(message) => {
+                    eprintln!("Error: {}", message);
+                    process::exit(1);
+                }
+            };
+            let mut reader = match verify_against_profile(profile.as_deref(), reader) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    process::exit(1);
+                }
+            };
+            if file_ops::is_stream1_file(&input) {
+                file_ops::decrypt_file_stream(&input, &output, &mut *reader, &mut *make_progress())
+            } else if file_ops::is_multi1_file(&input) {
+                file_ops::decrypt_file_multi(&input, &output, &mut *reader)
+            } else {
+                file_ops::decrypt_file(&input, &output, &mut *reader)
+            }
+        }
+        Commands::Update { input, output, profile } => {
+            let reader = match get_passphrase_reader(
+                cli.passphrase_stdin,
+                true,
+                askpass,
+                passphrase_file,
+                passphrase_env,
+                normalize,
+                None,
+            ) {
+                Ok(reader) => reader,
+                Err(message) => {
+                    eprintln!("Error: {}", message);
+                    process::exit(1);
+                }
+            };
+            let mut reader = match verify_against_profile(profile.as_deref(), reader) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    process::exit(1);
+                }
+            };
+            file_ops::update_file(&input, &output, &mut *reader)
+        }
+        Commands::AddRecipient {
+            file,
+            existing_recipient_passphrase_file,
+            new_recipient_passphrase_file,
+        } => {
+            let mut existing_reader = EnvelopePassphraseReader::new(existing_recipient_passphrase_file);
+            let mut new_reader = EnvelopePassphraseReader::new(new_recipient_passphrase_file);
+            file_ops::add_recipient_file(&file, &mut existing_reader, &mut new_reader)
+        }
+        Commands::RemoveRecipient {
+            file,
+            recipient_passphrase_file,
+        } => {
+            let mut reader = EnvelopePassphraseReader::new(recipient_passphrase_file);
+            file_ops::remove_recipient_file(&file, &mut reader)
+        }
+        Commands::Init {
+            name,
+            kdf,
+            argon2_m_cost,
+            argon2_t_cost,
+            argon2_p_cost,
+            allow_weak_passphrase,
+            min_passphrase_bits,
+        } => {
+            let kdf = match kdf {
+                Some(choice) => match kdf_from_flags(choice, argon2_m_cost, argon2_t_cost, argon2_p_cost) {
+                    Ok(kdf) => kdf,
+                    Err(message) => {
+                        eprintln!("Error: {}", message);
+                        process::exit(1);
+                    }
+                },
+                None => Kdf::SCRYPT_DEFAULT,
+            };
+            let min_entropy_bits = if allow_weak_passphrase {
+                None
+            } else {
+                Some(min_passphrase_bits.unwrap_or(DEFAULT_MIN_PASSPHRASE_BITS))
+            };
+            let mut reader = match get_passphrase_reader(
+                cli.passphrase_stdin,
+                true,
+                askpass,
+                passphrase_file,
+                passphrase_env,
+                normalize,
+                min_entropy_bits,
+            ) {
+                Ok(reader) => reader,
+                Err(message) => {
+                    eprintln!("Error: {}", message);
+                    process::exit(1);
+                }
+            };
+            init_profile(&name, kdf, &mut *reader)
+        }
+        Commands::Generate {
+            words,
+            length,
+            dice,
+        } => {
+            let passphrase = if let Some(wordlist) = dice {
+                passgen::generate_diceware_passphrase(
+                    &wordlist,
+                    words.unwrap_or(DEFAULT_GENERATE_WORDS),
+                )
+            } else {
+                passgen::generate_character_passphrase(length.unwrap_or(DEFAULT_GENERATE_LENGTH))
+            };
+
+            passphrase.and_then(|passphrase| {
+                use std::io::Write;
+                let mut stdout = std::io::stdout();
+                stdout
+                    .write_all(&passphrase)
+                    .and_then(|_| stdout.write_all(b"\n"))
+                    .map_err(|e| {
+                        saltybox::SaltyboxError::with_kind_and_source(
+                            saltybox::ErrorCategory::Internal,
+                            saltybox::ErrorKind::Io,
+                            format!("failed to write passphrase to stdout: {}", e),
+                            e,
+                        )
+                    })
+            })
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {:#}", e);
+        process::exit(1);
+    }
+}
+
+/// Build `ScryptParams` from the CLI's `--scrypt-n`/`--scrypt-r`/`--scrypt-p`
+/// flags, filling in [`ScryptParams::INTERACTIVE`] defaults for any that
+/// weren't given, and converting `n` to log2(n).
+fn scrypt_params_from_flags(
+    n: Option<u32>,
+    r: Option<u8>,
+    p: Option<u8>,
+) -> Result<ScryptParams, String> {
+    let default = ScryptParams::INTERACTIVE;
+
+    let log2_n = match n {
+        Some(n) => {
+            if !n.is_power_of_two() {
+                return Err(format!("--scrypt-n must be a power of two, got {}", n));
+            }
+            n.trailing_zeros() as u8
+        }
+        None => default.log2_n,
+    };
+
+    Ok(ScryptParams {
+        log2_n,
+        r: r.unwrap_or(default.r),
+        p: p.unwrap_or(default.p),
+    })
+}
+
+/// Builds a [`Kdf`] from `--kdf` and the `--argon2-*` tuning flags.
+fn kdf_from_flags(
+    choice: KdfChoice,
+    m_cost: Option<u32>,
+    t_cost: Option<u32>,
+    p_cost: Option<u32>,
+) -> Result<Kdf, String> {
+    match choice {
+        KdfChoice::Scrypt => {
+            if m_cost.is_some() || t_cost.is_some() || p_cost.is_some() {
+                return Err("--argon2-* flags require --kdf argon2id".to_string());
+            }
+            Ok(Kdf::SCRYPT_DEFAULT)
+        }
+        KdfChoice::Argon2id => {
+            let Kdf::Argon2id {
+                m_cost: default_m,
+                t_cost: default_t,
+                p_cost: default_p,
+            } = Kdf::ARGON2ID_DEFAULT
+            else {
+                unreachable!("ARGON2ID_DEFAULT is always an Argon2id variant")
+            };
+            Ok(Kdf::Argon2id {
+                m_cost: m_cost.unwrap_or(default_m),
+                t_cost: t_cost.unwrap_or(default_t),
+                p_cost: p_cost.unwrap_or(default_p),
+            })
+        }
+    }
+}
+
+/// Derives a new profile named `name` from a passphrase read via
+/// `passphrase_reader` and `kdf`, and saves it (see [`profile::Profile::init`]).
+fn init_profile(name: &str, kdf: Kdf, passphrase_reader: &mut dyn PassphraseReader) -> saltybox::Result<()> {
+    let passphrase = passphrase_reader.read_passphrase()?;
+    let new_profile = profile::Profile::init(&passphrase, kdf)?;
+    profile::ensure_config_dir()?;
+    new_profile.save(&profile::profile_path(name)?)
+}
+
+/// Loads the profile named `name`, verifies `passphrase_reader`'s
+/// passphrase against it, and encrypts using that profile's saved KDF and
+/// parameters (see [`file_ops::encrypt_file_with_kdf`]).
+fn encrypt_with_profile(
+    input: &std::path::Path,
+    output: &std::path::Path,
+    name: &str,
+    passphrase_reader: &mut dyn PassphraseReader,
+) -> saltybox::Result<()> {
+    let loaded = profile::Profile::load(&profile::profile_path(name)?)?;
+    let passphrase = passphrase_reader.read_passphrase()?;
+    loaded.verify(&passphrase)?;
+    let kdf = loaded.kdf()?;
+    let mut reader = ConstantPassphraseReader::new(passphrase.to_vec());
+    file_ops::encrypt_file_with_kdf(input, output, &mut reader, kdf)
+}
+
+/// If `profile_name` is set, reads one passphrase from `reader`, verifies
+/// it against that profile (see [`profile::Profile::verify`]) for a fast,
+/// unambiguous "wrong passphrase" error, and returns a reader that replays
+/// the same passphrase. Otherwise returns `reader` unchanged.
+fn verify_against_profile(
+    profile_name: Option<&str>,
+    reader: Box<dyn PassphraseReader>,
+) -> saltybox::Result<Box<dyn PassphraseReader>> {
+    let Some(name) = profile_name else {
+        return Ok(reader);
+    };
+    let loaded = profile::Profile::load(&profile::profile_path(name)?)?;
+    let mut reader = reader;
+    let passphrase = reader.read_passphrase()?;
+    loaded.verify(&passphrase)?;
+    Ok(Box::new(ConstantPassphraseReader::new(passphrase.to_vec())))
+}
+
+/// Builds the progress reporter used for streaming encrypt/decrypt: a live
+/// terminal bar when stderr is a terminal, otherwise a silent no-op so
+/// piped/scripted usage doesn't get progress-bar escape codes mixed into
+/// its output.
+fn make_progress() -> Box<dyn Progress> {
+    if std::io::stderr().is_terminal() {
+        Box::new(progress::TerminalProgress::new())
+    } else {
+        Box::new(progress::SilentProgress)
+    }
+}
+
+/// Builds the passphrase reader for a subcommand.
+///
+/// `confirm` requests double-entry confirmation (via
+/// [`ConfirmingPassphraseReader`]) for fresh passphrases, e.g. when
+/// encrypting; it only applies when reading interactively from the
+/// terminal, since every other source already returns a fixed value that
+/// would trivially "confirm" itself. `askpass`, `passphrase_file`, and
+/// `passphrase_env` (or `$SALTYBOX_ASKPASS`) each name an alternate,
+/// non-interactive passphrase source; at most one of
+/// `--passphrase-stdin`/`--askpass`/`--passphrase-file`/`--passphrase-env`
+/// may be specified at a time. `normalize` is applied to whichever source
+/// is chosen, before any confirmation comparison. `min_entropy_bits`, if
+/// set, rejects the resulting passphrase when its estimated entropy (see
+/// [`saltybox::passphrase::estimate_passphrase_bits`]) falls below it;
+/// pass `None` for callers (decrypt, update) that read an existing
+/// passphrase rather than having the user choose a new one.
+fn get_passphrase_reader(
+    use_stdin: bool,
+    confirm: bool,
+    askpass: Option<PathBuf>,
+    passphrase_file: Option<PathBuf>,
+    passphrase_env: Option<String>,
+    normalize: NormalizationPolicy,
+    min_entropy_bits: Option<f64>,
+) -> Result<Box<dyn PassphraseReader>, String> {
+    let askpass = askpass.or_else(|| std::env::var_os("SALTYBOX_ASKPASS").map(PathBuf::from));
+
+    let sources_specified = [
+        use_stdin,
+        askpass.is_some(),
+        passphrase_file.is_some(),
+        passphrase_env.is_some(),
+    ]
+    .iter()
+    .filter(|&&specified| specified)
+    .count();
+    if sources_specified > 1 {
+        return Err(
+            "only one of --passphrase-stdin, --askpass, --passphrase-file, --passphrase-env \
+             (or $SALTYBOX_ASKPASS) may be specified at a time"
+                .to_string(),
+        );
+    }
+
+    let interactive = !use_stdin && askpass.is_none() && passphrase_file.is_none() && passphrase_env.is_none();
+
+    let mut base: Box<dyn PassphraseReader> = if let Some(program) = askpass {
+        Box::new(CommandPassphraseReader::new(program.into_os_string()))
+    } else if let Some(path) = passphrase_file {
+        Box::new(EnvelopePassphraseReader::new(path))
+    } else if let Some(var) = passphrase_env {
+        Box::new(EnvVarPassphraseReader::new(var))
+    } else if use_stdin {
+        Box::new(ReaderPassphraseReader::new(Box::new(std::io::stdin())))
+    } else {
+        Box::new(TerminalPassphraseReader::new())
+    };
+
+    if normalize != NormalizationPolicy::NONE {
+        base = Box::new(NormalizingPassphraseReader::new(base, normalize));
+    }
+
+    if confirm && interactive {
+        base = Box::new(ConfirmingPassphraseReader::new(base));
+    }
+
+    base = match min_entropy_bits {
+        Some(min_bits) => Box::new(EnforcingPassphraseReader::new(base, min_bits)),
+        None => Box::new(EnforcingPassphraseReader::disabled(base)),
+    };
+
+    Ok(base)
+}
@@ -28,6 +28,8 @@ pub enum ErrorKind {
     ArmoringDecode,
     /// Input claimed to be saltybox but used a future/unsupported version.
     ArmoringFromFuture,
+    /// The armor's embedded checksum did not match the decoded payload.
+    ArmoringChecksumMismatch,
     /// Plaintext/ciphertext length fields or binary layout are invalid.
     BinaryFormat,
     /// Input data ended before the expected component could be read.
@@ -39,6 +41,12 @@ pub enum ErrorKind {
     AuthenticationFailed,
     /// Passphrase could not be obtained from the configured reader.
     PassphraseUnavailable,
+    /// A passphrase confirmation did not match the originally entered passphrase.
+    PassphraseMismatch,
+    /// A caller-supplied argument or parameter was out of range or otherwise invalid.
+    InvalidArgument,
+    /// A passphrase's estimated entropy fell below the required minimum.
+    WeakPassphrase,
     /// Low-level scrypt key derivation failed.
     ScryptFailure,
     /// NaCl secretbox (XSalsa20Poly1305) failed to seal or open data.
@@ -47,6 +55,9 @@ pub enum ErrorKind {
     InternalInvariant,
     /// Interaction with the filesystem, stdin/stdout, or other I/O failed.
     Io,
+    /// An output path's directory chain is not trusted (wrong owner, or
+    /// writable by a user other than its owner).
+    UntrustedDirectory,
 }
 
 #[derive(Debug, Error)]
@@ -0,0 +1,28 @@
+//! Saltybox - Passphrase-based file encryption using NaCl secretbox
+//!
+//! This is a Rust implementation of saltybox, maintaining exact compatibility
+//! with the original Go implementation's on-disk format.
+//!
+//! - `secretcrypt`: core encryption/decryption using scrypt + XSalsa20Poly1305
+//! - `varmor`: versioned base64 armoring with a version-prefixed magic marker
+//! - `passphrase`: passphrase acquisition from terminal, pipes, and other sources
+//! - `passgen`: generation of strong character and diceware-style passphrases
+//! - `file_ops`: high-level encrypt/decrypt/update operations over files
+//! - `pathtrust`: directory-ownership/permission checks before writing secrets
+//! - `pkcrypt`: public-key (recipient) encryption using X25519 sealed boxes
+//! - `profile`: reusable KDF-parameter + passphrase-verifier profiles
+//! - `progress`: progress reporting for long-running streaming operations
+//! - `error`: shared error type used across the crate
+
+pub mod error;
+pub mod file_ops;
+pub mod passgen;
+pub mod passphrase;
+pub mod pathtrust;
+pub mod pkcrypt;
+pub mod profile;
+pub mod progress;
+pub mod secretcrypt;
+pub mod varmor;
+
+pub use error::{ErrorCategory, ErrorKind, Result, SaltyboxError};
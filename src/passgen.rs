@@ -0,0 +1,205 @@
+//! Passphrase generation: strong random character passphrases, and
+//! diceware-style passphrases drawn from a wordlist.
+
+use crate::error::{ErrorCategory, ErrorKind, Result, SaltyboxError};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const DIGITS: &[u8] = b"0123456789";
+const SYMBOLS: &[u8] = b"!@#$%^&*()-_=+[]{}";
+
+/// Tracks how many characters of each class a generated passphrase contains,
+/// so generation can be retried until every class is represented.
+#[derive(Debug, Default, Clone, Copy)]
+struct CharDistro {
+    upper: u32,
+    lower: u32,
+    digit: u32,
+    symbol: u32,
+}
+
+impl CharDistro {
+    fn record(&mut self, byte: u8) {
+        if UPPERCASE.contains(&byte) {
+            self.upper += 1;
+        } else if LOWERCASE.contains(&byte) {
+            self.lower += 1;
+        } else if DIGITS.contains(&byte) {
+            self.digit += 1;
+        } else if SYMBOLS.contains(&byte) {
+            self.symbol += 1;
+        }
+    }
+
+    fn all_classes_present(&self) -> bool {
+        self.upper > 0 && self.lower > 0 && self.digit > 0 && self.symbol > 0
+    }
+}
+
+/// Draws a uniformly random index in `0..bound` from `OsRng` using
+/// rejection sampling, so the result isn't biased towards low indices the
+/// way a plain `next_u32() % bound` would be.
+fn random_index(bound: usize) -> usize {
+    let bound = bound as u32;
+    let limit = u32::MAX - (u32::MAX % bound);
+    loop {
+        let value = OsRng.next_u32();
+        if value < limit {
+            return (value % bound) as usize;
+        }
+    }
+}
+
+/// Generates a `length`-byte passphrase drawn from uppercase, lowercase,
+/// digit, and symbol characters, regenerating until all four classes are
+/// present at least once.
+pub fn generate_character_passphrase(length: u8) -> Result<Zeroizing<Vec<u8>>> {
+    if length < 4 {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::InvalidArgument,
+            format!(
+                "passphrase length must be at least 4 to fit all character classes, got {}",
+                length
+            ),
+        ));
+    }
+
+    let charset: Vec<u8> = [UPPERCASE, LOWERCASE, DIGITS, SYMBOLS].concat();
+
+    loop {
+        let mut passphrase = Zeroizing::new(Vec::with_capacity(length as usize));
+        let mut distro = CharDistro::default();
+        for _ in 0..length {
+            let byte = charset[random_index(charset.len())];
+            distro.record(byte);
+            passphrase.push(byte);
+        }
+        if distro.all_classes_present() {
+            return Ok(passphrase);
+        }
+    }
+}
+
+/// Generates a diceware-style passphrase of `words` words drawn uniformly
+/// (with rejection sampling) from a newline-delimited wordlist file, joined
+/// with spaces.
+pub fn generate_diceware_passphrase(wordlist_path: &Path, words: u8) -> Result<Zeroizing<Vec<u8>>> {
+    if words == 0 {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::InvalidArgument,
+            "passphrase must contain at least 1 word",
+        ));
+    }
+
+    let contents = std::fs::read_to_string(wordlist_path).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::User,
+            ErrorKind::Io,
+            format!(
+                "failed to read wordlist {}: {}",
+                wordlist_path.display(),
+                e
+            ),
+            e,
+        )
+    })?;
+    let wordlist: Vec<&str> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if wordlist.is_empty() {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::InvalidArgument,
+            format!("wordlist {} contains no words", wordlist_path.display()),
+        ));
+    }
+
+    let mut passphrase = Zeroizing::new(Vec::new());
+    for i in 0..words {
+        if i > 0 {
+            passphrase.push(b' ');
+        }
+        let word = wordlist[random_index(wordlist.len())];
+        passphrase.extend_from_slice(word.as_bytes());
+    }
+    Ok(passphrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_character_passphrase_length() {
+        let passphrase = generate_character_passphrase(20).unwrap();
+        assert_eq!(passphrase.len(), 20);
+    }
+
+    #[test]
+    fn test_generate_character_passphrase_has_all_classes() {
+        let passphrase = generate_character_passphrase(16).unwrap();
+        let mut distro = CharDistro::default();
+        for &byte in passphrase.iter() {
+            distro.record(byte);
+        }
+        assert!(distro.all_classes_present());
+    }
+
+    #[test]
+    fn test_generate_character_passphrase_rejects_too_short() {
+        let err = generate_character_passphrase(3).unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::InvalidArgument));
+    }
+
+    #[test]
+    fn test_generate_character_passphrase_is_random() {
+        let a = generate_character_passphrase(20).unwrap();
+        let b = generate_character_passphrase(20).unwrap();
+        assert_ne!(&*a, &*b);
+    }
+
+    #[test]
+    fn test_generate_diceware_passphrase() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        use std::io::Write as _;
+        writeln!(file, "apple\nbanana\ncherry\ndurian").unwrap();
+
+        let passphrase = generate_diceware_passphrase(file.path(), 4).unwrap();
+        let words: Vec<&[u8]> = passphrase.split(|&b| b == b' ').collect();
+        assert_eq!(words.len(), 4);
+        for word in words {
+            assert!([b"apple" as &[u8], b"banana", b"cherry", b"durian"].contains(&word));
+        }
+    }
+
+    #[test]
+    fn test_generate_diceware_passphrase_rejects_zero_words() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        use std::io::Write as _;
+        writeln!(file, "apple").unwrap();
+
+        let err = generate_diceware_passphrase(file.path(), 0).unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::InvalidArgument));
+    }
+
+    #[test]
+    fn test_generate_diceware_passphrase_rejects_empty_wordlist() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let err = generate_diceware_passphrase(file.path(), 2).unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::InvalidArgument));
+    }
+
+    #[test]
+    fn test_generate_diceware_passphrase_missing_file() {
+        let err = generate_diceware_passphrase(Path::new("/nonexistent/wordlist"), 2).unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::Io));
+    }
+}
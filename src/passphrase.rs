@@ -1,7 +1,12 @@
 //! Passphrase reading functionality
 
 use crate::error::{ErrorCategory, ErrorKind, Result, SaltyboxError};
+use std::ffi::OsString;
+use std::fs;
 use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use unicode_normalization::UnicodeNormalization;
 use zeroize::Zeroizing;
 
 /// Trait for reading passphrases from various sources
@@ -120,6 +125,128 @@ impl PassphraseReader for TerminalPassphraseReader {
     }
 }
 
+/// Reads a passphrase by invoking an external askpass/pinentry-style helper
+/// program and taking its stdout as the passphrase.
+///
+/// This mirrors how `ssh-askpass` and similar tools work: the helper is
+/// responsible for prompting the user however it sees fit (a GUI dialog, a
+/// password manager, a hardware token), and simply prints the result. A
+/// single trailing newline is trimmed; a nonzero exit status is treated as
+/// a user error rather than a passphrase value.
+pub struct CommandPassphraseReader {
+    program: OsString,
+}
+
+impl CommandPassphraseReader {
+    pub fn new(program: impl Into<OsString>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+}
+
+impl PassphraseReader for CommandPassphraseReader {
+    fn read_passphrase(&mut self) -> Result<Zeroizing<Vec<u8>>> {
+        let output = Command::new(&self.program).output().map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::User,
+                ErrorKind::PassphraseUnavailable,
+                format!("failed to run askpass program {:?}: {}", self.program, e),
+                e,
+            )
+        })?;
+
+        if !output.status.success() {
+            return Err(SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::PassphraseUnavailable,
+                format!(
+                    "askpass program {:?} exited with {}",
+                    self.program, output.status
+                ),
+            ));
+        }
+
+        let mut passphrase = Zeroizing::new(output.stdout);
+        if passphrase.last() == Some(&b'\n') {
+            passphrase.pop();
+        }
+        Ok(passphrase)
+    }
+}
+
+/// Reads a passphrase from the raw bytes of a file, given by
+/// `--passphrase-file`.
+///
+/// A single trailing newline is stripped if present (common when the file
+/// was created with a plain `echo` or text editor); any other content,
+/// including non-UTF-8 bytes, is passed through verbatim.
+pub struct EnvelopePassphraseReader {
+    path: PathBuf,
+}
+
+impl EnvelopePassphraseReader {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl PassphraseReader for EnvelopePassphraseReader {
+    fn read_passphrase(&mut self) -> Result<Zeroizing<Vec<u8>>> {
+        let data = fs::read(&self.path).map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::User,
+                ErrorKind::PassphraseUnavailable,
+                format!(
+                    "failed to read passphrase file {}: {}",
+                    self.path.display(),
+                    e
+                ),
+                e,
+            )
+        })?;
+        let mut passphrase = Zeroizing::new(data);
+        if passphrase.last() == Some(&b'\n') {
+            passphrase.pop();
+        }
+        Ok(passphrase)
+    }
+}
+
+/// Reads a passphrase from the raw bytes of an environment variable, given
+/// by `--passphrase-env`.
+pub struct EnvVarPassphraseReader {
+    var: OsString,
+}
+
+impl EnvVarPassphraseReader {
+    pub fn new(var: impl Into<OsString>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl PassphraseReader for EnvVarPassphraseReader {
+    fn read_passphrase(&mut self) -> Result<Zeroizing<Vec<u8>>> {
+        let value = std::env::var_os(&self.var).ok_or_else(|| {
+            SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::PassphraseUnavailable,
+                format!("environment variable {:?} is not set", self.var),
+            )
+        })?;
+
+        #[cfg(unix)]
+        let bytes = {
+            use std::os::unix::ffi::OsStrExt;
+            value.as_bytes().to_vec()
+        };
+        #[cfg(not(unix))]
+        let bytes = value.to_string_lossy().into_owned().into_bytes();
+
+        Ok(Zeroizing::new(bytes))
+    }
+}
+
 /// Wraps another PassphraseReader and caches the result
 ///
 /// Provides "at most once" semantics - the upstream reader is called
@@ -151,6 +278,298 @@ impl PassphraseReader for CachingPassphraseReader {
     }
 }
 
+/// Wraps another `PassphraseReader` and requires the passphrase to be
+/// entered twice, guarding against a mistyped passphrase that would
+/// otherwise permanently lock the user out of their own data.
+///
+/// The upstream reader is invoked twice; its first prompt is whatever the
+/// upstream itself prints (typically "Passphrase (saltybox): "), and a
+/// "Confirm passphrase: " prompt is printed before the second read. If the
+/// two results don't match byte-for-byte, `read_passphrase` fails with
+/// `ErrorKind::PassphraseMismatch` rather than returning either buffer.
+pub struct ConfirmingPassphraseReader {
+    upstream: Box<dyn PassphraseReader>,
+}
+
+impl ConfirmingPassphraseReader {
+    pub fn new(upstream: Box<dyn PassphraseReader>) -> Self {
+        Self { upstream }
+    }
+}
+
+impl PassphraseReader for ConfirmingPassphraseReader {
+    fn read_passphrase(&mut self) -> Result<Zeroizing<Vec<u8>>> {
+        let first = self.upstream.read_passphrase()?;
+
+        io::stderr().write_all(b"Confirm passphrase: ").map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::Internal,
+                ErrorKind::Io,
+                format!("failed to write prompt: {}", e),
+                e,
+            )
+        })?;
+        io::stderr().flush().map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::Internal,
+                ErrorKind::Io,
+                format!("failed to flush prompt: {}", e),
+                e,
+            )
+        })?;
+
+        let second = self.upstream.read_passphrase()?;
+
+        if !constant_time_eq(&first, &second) {
+            return Err(SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::PassphraseMismatch,
+                "passphrases did not match",
+            ));
+        }
+
+        Ok(first)
+    }
+}
+
+/// Compares two byte slices for equality without short-circuiting on the
+/// first differing byte, so the time taken does not leak how much of the
+/// confirmation matched the original passphrase.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+/// Default minimum estimated entropy, in bits, a passphrase must reach
+/// before [`EnforcingPassphraseReader`] accepts it.
+pub const DEFAULT_MIN_PASSPHRASE_BITS: f64 = 128.0;
+
+/// Estimates the entropy, in bits, of `passphrase` from the character
+/// classes it draws from and its length, discounted for repeated or
+/// sequential runs (e.g. "aaaa" or "1234") that a naive `log2(charset) *
+/// length` estimate would overcount.
+///
+/// This is a heuristic meant to catch the most common catastrophically
+/// weak passphrases, not a rigorous measurement of guessing resistance;
+/// see [`EnforcingPassphraseReader`] for how it's used to gate `encrypt`.
+pub fn estimate_passphrase_bits(passphrase: &[u8]) -> f64 {
+    if passphrase.is_empty() {
+        return 0.0;
+    }
+
+    let mut charset_size: u32 = 0;
+    if passphrase.iter().any(u8::is_ascii_lowercase) {
+        charset_size += 26;
+    }
+    if passphrase.iter().any(u8::is_ascii_uppercase) {
+        charset_size += 26;
+    }
+    if passphrase.iter().any(u8::is_ascii_digit) {
+        charset_size += 10;
+    }
+    if passphrase
+        .iter()
+        .any(|b| !b.is_ascii_alphanumeric() && b.is_ascii_graphic())
+    {
+        charset_size += 33;
+    }
+    if passphrase.iter().any(|b| !b.is_ascii_graphic()) {
+        // Non-printable or non-ASCII bytes (e.g. multi-byte UTF-8): treat
+        // each such byte as drawn from the full byte range.
+        charset_size += 256;
+    }
+    let charset_size = charset_size.max(2);
+
+    // Discount each byte that merely repeats or continues a +1/-1
+    // sequential run from the byte before it ("aaaa", "1234", "4321"), so
+    // runs like that contribute a shrinking fraction of a fresh byte's
+    // entropy instead of being counted at full weight.
+    let mut effective_len = 1.0;
+    let mut run_len: u32 = 1;
+    for window in passphrase.windows(2) {
+        let (prev, cur) = (window[0] as i16, window[1] as i16);
+        let continues_run = cur == prev || cur == prev + 1 || cur == prev - 1;
+        run_len = if continues_run { run_len + 1 } else { 1 };
+        effective_len += 1.0 / run_len as f64;
+    }
+
+    (charset_size as f64).log2() * effective_len
+}
+
+/// Wraps an upstream [`PassphraseReader`] and rejects passphrases whose
+/// estimated entropy (see [`estimate_passphrase_bits`]) falls below
+/// `min_bits`, failing with `ErrorKind::WeakPassphrase` instead of
+/// returning them. Construct with [`EnforcingPassphraseReader::disabled`]
+/// to let any passphrase through unchecked, for callers who know their
+/// input is already high-entropy (e.g. a machine-generated key) and don't
+/// want it second-guessed by the heuristic.
+pub struct EnforcingPassphraseReader {
+    upstream: Box<dyn PassphraseReader>,
+    min_bits: Option<f64>,
+}
+
+impl EnforcingPassphraseReader {
+    /// Rejects passphrases estimated below `min_bits`; see
+    /// [`DEFAULT_MIN_PASSPHRASE_BITS`] for the repo's default threshold.
+    pub fn new(upstream: Box<dyn PassphraseReader>, min_bits: f64) -> Self {
+        Self {
+            upstream,
+            min_bits: Some(min_bits),
+        }
+    }
+
+    /// Disables entropy enforcement entirely; `read_passphrase` simply
+    /// delegates to `upstream`.
+    pub fn disabled(upstream: Box<dyn PassphraseReader>) -> Self {
+        Self {
+            upstream,
+            min_bits: None,
+        }
+    }
+}
+
+impl PassphraseReader for EnforcingPassphraseReader {
+    fn read_passphrase(&mut self) -> Result<Zeroizing<Vec<u8>>> {
+        let passphrase = self.upstream.read_passphrase()?;
+
+        if let Some(min_bits) = self.min_bits {
+            let bits = estimate_passphrase_bits(&passphrase);
+            if bits < min_bits {
+                return Err(SaltyboxError::with_kind(
+                    ErrorCategory::User,
+                    ErrorKind::WeakPassphrase,
+                    format!(
+                        "passphrase has an estimated {:.0} bits of entropy, below the required minimum of {:.0}",
+                        bits, min_bits
+                    ),
+                ));
+            }
+        }
+
+        Ok(passphrase)
+    }
+}
+
+/// Controls how [`NormalizingPassphraseReader`] adjusts passphrase bytes
+/// before returning them.
+///
+/// The same human-typed passphrase can otherwise hash to different keys
+/// depending on input path or OS keyboard layout (e.g. `TerminalPassphraseReader`
+/// goes through rpassword's UTF-8 string handling, while `--passphrase-stdin`
+/// passes bytes through untouched). All fields default to `false`, which
+/// leaves bytes completely unchanged so existing files remain decryptable.
+///
+/// Changing normalization between encrypting and decrypting a file changes
+/// the derived key and makes the file undecryptable, even with an
+/// otherwise-correct passphrase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NormalizationPolicy {
+    /// Apply Unicode Normalization Form C to valid-UTF-8 input.
+    pub nfc: bool,
+    /// Trim trailing ASCII whitespace.
+    pub trim: bool,
+    /// Strip the high bit of every byte, folding input to 7-bit ASCII
+    /// (mirrors `readpassphrase`'s `RPP_SEVENBIT`).
+    pub seven_bit: bool,
+}
+
+impl NormalizationPolicy {
+    /// No normalization: bytes pass through unchanged.
+    pub const NONE: Self = Self {
+        nfc: false,
+        trim: false,
+        seven_bit: false,
+    };
+
+    fn is_noop(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
+/// Parses a comma-separated `--normalize` value (e.g. `"nfc,trim"`) into a
+/// [`NormalizationPolicy`]. Recognized tokens are `nfc`, `trim`, and
+/// `sevenbit`.
+pub fn parse_normalization_policy(spec: &str) -> Result<NormalizationPolicy> {
+    let mut policy = NormalizationPolicy::NONE;
+    for token in spec.split(',') {
+        let token = token.trim();
+        if token.is_empty() {
+            continue;
+        }
+        match token {
+            "nfc" => policy.nfc = true,
+            "trim" => policy.trim = true,
+            "sevenbit" => policy.seven_bit = true,
+            other => {
+                return Err(SaltyboxError::with_kind(
+                    ErrorCategory::User,
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "unrecognized --normalize option {:?} (expected nfc, trim, or sevenbit)",
+                        other
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(policy)
+}
+
+/// Wraps another `PassphraseReader` and applies a [`NormalizationPolicy`]
+/// to the bytes it returns.
+///
+/// Intermediate `String` buffers created for NFC normalization are wrapped
+/// in `Zeroizing` so normalized/un-normalized copies of the passphrase
+/// don't linger in memory. When the policy is a no-op, the upstream bytes
+/// are returned completely untouched.
+pub struct NormalizingPassphraseReader {
+    upstream: Box<dyn PassphraseReader>,
+    policy: NormalizationPolicy,
+}
+
+impl NormalizingPassphraseReader {
+    pub fn new(upstream: Box<dyn PassphraseReader>, policy: NormalizationPolicy) -> Self {
+        Self { upstream, policy }
+    }
+}
+
+impl PassphraseReader for NormalizingPassphraseReader {
+    fn read_passphrase(&mut self) -> Result<Zeroizing<Vec<u8>>> {
+        let mut passphrase = self.upstream.read_passphrase()?;
+
+        if self.policy.is_noop() {
+            return Ok(passphrase);
+        }
+
+        if self.policy.seven_bit {
+            for byte in passphrase.iter_mut() {
+                *byte &= 0x7f;
+            }
+        }
+
+        if self.policy.nfc {
+            if let Ok(text) = std::str::from_utf8(&passphrase) {
+                let normalized: Zeroizing<String> = Zeroizing::new(text.nfc().collect());
+                passphrase = Zeroizing::new(normalized.as_bytes().to_vec());
+            }
+        }
+
+        if self.policy.trim {
+            while matches!(passphrase.last(), Some(b) if b.is_ascii_whitespace()) {
+                passphrase.pop();
+            }
+        }
+
+        Ok(passphrase)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,4 +682,275 @@ mod tests {
         // Error should not be cached - subsequent call should try again
         assert!(caching.read_passphrase().is_err());
     }
+
+    /// A reader that returns a different passphrase on each successive call,
+    /// used to simulate a user mistyping the confirmation.
+    struct SequenceReader {
+        passphrases: std::collections::VecDeque<Vec<u8>>,
+    }
+
+    impl SequenceReader {
+        fn new(passphrases: Vec<&[u8]>) -> Self {
+            Self {
+                passphrases: passphrases.into_iter().map(|p| p.to_vec()).collect(),
+            }
+        }
+    }
+
+    impl PassphraseReader for SequenceReader {
+        fn read_passphrase(&mut self) -> Result<Zeroizing<Vec<u8>>> {
+            Ok(Zeroizing::new(
+                self.passphrases
+                    .pop_front()
+                    .expect("SequenceReader exhausted"),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_confirming_reader_accepts_matching_passphrases() {
+        let upstream = SequenceReader::new(vec![b"correct horse", b"correct horse"]);
+        let mut reader = ConfirmingPassphraseReader::new(Box::new(upstream));
+        assert_eq!(&*reader.read_passphrase().unwrap(), b"correct horse");
+    }
+
+    #[test]
+    fn test_confirming_reader_rejects_mismatched_passphrases() {
+        let upstream = SequenceReader::new(vec![b"correct horse", b"battery staple"]);
+        let mut reader = ConfirmingPassphraseReader::new(Box::new(upstream));
+        let err = reader.read_passphrase().unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::PassphraseMismatch));
+        assert_eq!(err.category, ErrorCategory::User);
+    }
+
+    #[test]
+    fn test_confirming_reader_rejects_different_length_passphrases() {
+        let upstream = SequenceReader::new(vec![b"short", b"much longer passphrase"]);
+        let mut reader = ConfirmingPassphraseReader::new(Box::new(upstream));
+        assert!(reader.read_passphrase().is_err());
+    }
+
+    #[test]
+    fn test_estimate_passphrase_bits_empty_is_zero() {
+        assert_eq!(estimate_passphrase_bits(b""), 0.0);
+    }
+
+    #[test]
+    fn test_estimate_passphrase_bits_rejects_trivial_passphrase() {
+        assert!(estimate_passphrase_bits(b"1234") < DEFAULT_MIN_PASSPHRASE_BITS);
+        assert!(estimate_passphrase_bits(b"aaaaaaaaaaaaaaaaaaaa") < DEFAULT_MIN_PASSPHRASE_BITS);
+    }
+
+    #[test]
+    fn test_estimate_passphrase_bits_accepts_long_random_passphrase() {
+        let strong = b"correct-Horse_battery27STAPLE!ambling";
+        assert!(estimate_passphrase_bits(strong) >= DEFAULT_MIN_PASSPHRASE_BITS);
+    }
+
+    #[test]
+    fn test_estimate_passphrase_bits_discounts_sequential_runs() {
+        let sequential = estimate_passphrase_bits(b"abcdefghijklmnopqrst");
+        let shuffled = estimate_passphrase_bits(b"hatbumvpwdxfsgjoyirc");
+        assert!(sequential < shuffled);
+    }
+
+    #[test]
+    fn test_enforcing_reader_rejects_weak_passphrase() {
+        let upstream = SequenceReader::new(vec![b"1234"]);
+        let mut reader =
+            EnforcingPassphraseReader::new(Box::new(upstream), DEFAULT_MIN_PASSPHRASE_BITS);
+        let err = reader.read_passphrase().unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::WeakPassphrase));
+        assert_eq!(err.category, ErrorCategory::User);
+    }
+
+    #[test]
+    fn test_enforcing_reader_accepts_strong_passphrase() {
+        let strong: &[u8] = b"correct-Horse_battery27STAPLE!ambling";
+        let upstream = SequenceReader::new(vec![strong]);
+        let mut reader =
+            EnforcingPassphraseReader::new(Box::new(upstream), DEFAULT_MIN_PASSPHRASE_BITS);
+        assert_eq!(&*reader.read_passphrase().unwrap(), strong);
+    }
+
+    #[test]
+    fn test_enforcing_reader_disabled_accepts_anything() {
+        let upstream = SequenceReader::new(vec![b"1234"]);
+        let mut reader = EnforcingPassphraseReader::disabled(Box::new(upstream));
+        assert_eq!(&*reader.read_passphrase().unwrap(), b"1234");
+    }
+
+    #[test]
+    fn test_command_passphrase_reader() {
+        let mut reader = CommandPassphraseReader::new("echo");
+        // `echo` with no args just prints a newline, i.e. an empty passphrase
+        // once the trailing newline is trimmed.
+        assert_eq!(&*reader.read_passphrase().unwrap(), b"");
+    }
+
+    /// Writes a trivial shell script to a fresh temp file that prints a
+    /// fixed passphrase followed by a newline, and returns its path.
+    fn write_fixed_output_script(output: &str) -> std::path::PathBuf {
+        let mut script = tempfile::NamedTempFile::new().unwrap();
+        use std::io::Write as _;
+        writeln!(script, "#!/bin/sh\nprintf '%s\\n' '{}'", output).unwrap();
+        let path = script.into_temp_path();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        path.keep().unwrap()
+    }
+
+    #[test]
+    fn test_command_passphrase_reader_trims_trailing_newline() {
+        let script = write_fixed_output_script("hunter2");
+        let mut reader = CommandPassphraseReader::new(script.as_os_str());
+        let result = reader.read_passphrase().unwrap();
+        std::fs::remove_file(&script).ok();
+        assert_eq!(&*result, b"hunter2");
+    }
+
+    #[test]
+    fn test_command_passphrase_reader_reports_nonzero_exit() {
+        let mut reader = CommandPassphraseReader::new("false");
+        let err = reader.read_passphrase().unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::PassphraseUnavailable));
+    }
+
+    #[test]
+    fn test_command_passphrase_reader_reports_missing_program() {
+        let mut reader = CommandPassphraseReader::new("saltybox-test-askpass-does-not-exist");
+        let err = reader.read_passphrase().unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::PassphraseUnavailable));
+    }
+
+    #[test]
+    fn test_envelope_passphrase_reader_strips_trailing_newline() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        use std::io::Write as _;
+        write!(file, "hunter2\n").unwrap();
+        let mut reader = EnvelopePassphraseReader::new(file.path());
+        assert_eq!(&*reader.read_passphrase().unwrap(), b"hunter2");
+    }
+
+    #[test]
+    fn test_envelope_passphrase_reader_keeps_content_without_trailing_newline() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        use std::io::Write as _;
+        write!(file, "hunter2").unwrap();
+        let mut reader = EnvelopePassphraseReader::new(file.path());
+        assert_eq!(&*reader.read_passphrase().unwrap(), b"hunter2");
+    }
+
+    #[test]
+    fn test_envelope_passphrase_reader_missing_file() {
+        let mut reader = EnvelopePassphraseReader::new("/nonexistent/path/to/passphrase");
+        assert!(reader.read_passphrase().is_err());
+    }
+
+    #[test]
+    fn test_env_var_passphrase_reader() {
+        // SAFETY: tests run single-threaded within this process for this var name;
+        // use a name unlikely to collide with anything else.
+        unsafe {
+            std::env::set_var("SALTYBOX_TEST_PASSPHRASE_VAR", "hunter2");
+        }
+        let mut reader = EnvVarPassphraseReader::new("SALTYBOX_TEST_PASSPHRASE_VAR");
+        let result = reader.read_passphrase().unwrap();
+        unsafe {
+            std::env::remove_var("SALTYBOX_TEST_PASSPHRASE_VAR");
+        }
+        assert_eq!(&*result, b"hunter2");
+    }
+
+    #[test]
+    fn test_env_var_passphrase_reader_missing_var() {
+        let mut reader = EnvVarPassphraseReader::new("SALTYBOX_TEST_PASSPHRASE_VAR_UNSET");
+        assert!(reader.read_passphrase().is_err());
+    }
+
+    #[test]
+    fn test_parse_normalization_policy_empty_is_noop() {
+        let policy = parse_normalization_policy("").unwrap();
+        assert_eq!(policy, NormalizationPolicy::NONE);
+    }
+
+    #[test]
+    fn test_parse_normalization_policy_combines_tokens() {
+        let policy = parse_normalization_policy("nfc,trim").unwrap();
+        assert!(policy.nfc);
+        assert!(policy.trim);
+        assert!(!policy.seven_bit);
+    }
+
+    #[test]
+    fn test_parse_normalization_policy_rejects_unknown_token() {
+        assert!(parse_normalization_policy("bogus").is_err());
+    }
+
+    #[test]
+    fn test_normalizing_reader_noop_leaves_bytes_untouched() {
+        let upstream = ConstantPassphraseReader::new(vec![0xff, 0x20, 0x41]);
+        let mut reader =
+            NormalizingPassphraseReader::new(Box::new(upstream), NormalizationPolicy::NONE);
+        assert_eq!(&*reader.read_passphrase().unwrap(), &[0xff, 0x20, 0x41]);
+    }
+
+    #[test]
+    fn test_normalizing_reader_trims_trailing_whitespace() {
+        let upstream = ConstantPassphraseReader::new(b"hunter2 \n".to_vec());
+        let policy = NormalizationPolicy {
+            trim: true,
+            ..NormalizationPolicy::NONE
+        };
+        let mut reader = NormalizingPassphraseReader::new(Box::new(upstream), policy);
+        assert_eq!(&*reader.read_passphrase().unwrap(), b"hunter2");
+    }
+
+    #[test]
+    fn test_normalizing_reader_strips_high_bit() {
+        let upstream = ConstantPassphraseReader::new(vec![0xe1, 0x61]);
+        let policy = NormalizationPolicy {
+            seven_bit: true,
+            ..NormalizationPolicy::NONE
+        };
+        let mut reader = NormalizingPassphraseReader::new(Box::new(upstream), policy);
+        assert_eq!(&*reader.read_passphrase().unwrap(), &[0x61, 0x61]);
+    }
+
+    #[test]
+    fn test_normalizing_reader_applies_nfc() {
+        // "e" + combining acute accent (NFD) should normalize to the
+        // precomposed "é" (NFC).
+        let nfd = "e\u{0301}".as_bytes().to_vec();
+        let upstream = ConstantPassphraseReader::new(nfd);
+        let policy = NormalizationPolicy {
+            nfc: true,
+            ..NormalizationPolicy::NONE
+        };
+        let mut reader = NormalizingPassphraseReader::new(Box::new(upstream), policy);
+        let result = reader.read_passphrase().unwrap();
+        assert_eq!(&*result, "\u{00e9}".as_bytes());
+    }
+
+    #[test]
+    fn test_normalizing_reader_skips_nfc_on_invalid_utf8() {
+        let upstream = ConstantPassphraseReader::new(vec![0xff, 0xfe]);
+        let policy = NormalizationPolicy {
+            nfc: true,
+            ..NormalizationPolicy::NONE
+        };
+        let mut reader = NormalizingPassphraseReader::new(Box::new(upstream), policy);
+        assert_eq!(&*reader.read_passphrase().unwrap(), &[0xff, 0xfe]);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert!(constant_time_eq(b"", b""));
+    }
 }
@@ -0,0 +1,260 @@
+//! Directory-trust checks performed before writing secrets to disk.
+//!
+//! `write_file_secure` and the tempfile dance in [`crate::file_ops`] set a
+//! restrictive mode on the file they produce, but that's no protection if
+//! the *directory* the file lands in is writable by another user: that user
+//! could swap a symlink in ahead of the write, or simply read a stray
+//! tempfile left behind by a crash. This module walks the parent directory
+//! chain of an output path and refuses to proceed if any existing ancestor
+//! is owned by someone other than the current user, or is writable by
+//! anyone other than its owner - loosely modeled on the directory-trust
+//! checks in Tor's `fs-mistrust` crate.
+//!
+//! The walk climbs only up to a trust boundary: `$HOME` if set, or
+//! otherwise the first ancestor not owned by the current user once at
+//! least one owned ancestor has been seen. Without a boundary, this check
+//! would reject writes into an ordinary user's own home directory on any
+//! system where `/`, `/home`, etc. are root-owned - which is every system.
+//! An ancestor that fails the *first* check (the one closest to the
+//! output path) is never given this pass, since that's exactly the
+//! symlink-swap / shared-tmpdir case this module exists to catch.
+//!
+//! Set [`TRUST_OVERRIDE_ENV_VAR`] in the environment to skip the check
+//! entirely, for callers who knowingly write into a shared location.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{ErrorCategory, ErrorKind, Result, SaltyboxError};
+
+/// Environment variable that, if set to any value, disables the directory-trust check.
+pub const TRUST_OVERRIDE_ENV_VAR: &str = "SALTYBOX_DANGEROUSLY_TRUST_PATH";
+
+/// `O_NOFOLLOW`, for passing to [`std::os::unix::fs::OpenOptionsExt::custom_flags`]
+/// so opening a secret's output path fails outright if its final component
+/// turns out to be a symlink, rather than silently writing through it.
+#[cfg(target_os = "linux")]
+pub const O_NOFOLLOW: i32 = 0o400_000;
+#[cfg(any(target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+pub const O_NOFOLLOW: i32 = 0x0000_0100;
+
+/// Verifies that the existing ancestors of `path`, up to the trust boundary
+/// described in the module docs, are owned by the current user and not
+/// writable by anyone else.
+///
+/// `path` itself need not exist; only its parent directories are checked.
+/// Ancestors that don't exist yet (directories our own write is about to
+/// create) are skipped: the walk starts at the deepest existing ancestor,
+/// resolved through any symlinks, and climbs from there.
+///
+/// A no-op if [`TRUST_OVERRIDE_ENV_VAR`] is set.
+pub fn check_trusted(path: &Path) -> Result<()> {
+    if std::env::var_os(TRUST_OVERRIDE_ENV_VAR).is_some() {
+        return Ok(());
+    }
+    check_trusted_inner(&absolute_path(path)?)
+}
+
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        return Ok(path.to_path_buf());
+    }
+    let cwd = std::env::current_dir().map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::Internal,
+            ErrorKind::Io,
+            "failed to get current directory",
+            e,
+        )
+    })?;
+    Ok(cwd.join(path))
+}
+
+#[cfg(unix)]
+fn check_trusted_inner(absolute: &Path) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    // Resolve the deepest existing ancestor to its canonical form first, so a
+    // symlinked directory earlier in the chain is actually followed to the
+    // directory it resolves to (and that directory's ownership/mode checked)
+    // rather than silently skipped because it's "just a symlink".
+    let mut existing = absolute.parent();
+    while let Some(candidate) = existing {
+        if candidate.exists() {
+            break;
+        }
+        existing = candidate.parent();
+    }
+    let Some(existing) = existing else {
+        return Ok(());
+    };
+    let canonical = std::fs::canonicalize(existing).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::Internal,
+            ErrorKind::Io,
+            format!("failed to resolve {}", existing.display()),
+            e,
+        )
+    })?;
+
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut ancestors = Vec::new();
+    let mut dir = Some(canonical.as_path());
+    while let Some(candidate) = dir {
+        let metadata = match std::fs::symlink_metadata(candidate) {
+            Ok(metadata) => metadata,
+            Err(_) => break,
+        };
+        ancestors.push((candidate.to_path_buf(), metadata.uid(), metadata.mode()));
+        if home.as_deref() == Some(candidate) {
+            break;
+        }
+        dir = candidate.parent();
+    }
+
+    evaluate_ancestors(current_uid(), &ancestors)
+}
+
+/// Applies the ownership/mode rules to an already-resolved ancestor chain
+/// (nearest ancestor first), stopping at the first untrusted boundary
+/// rather than erroring once at least one ancestor has already checked out
+/// as owned by `current_uid`. Separated from [`check_trusted_inner`] so the
+/// boundary logic can be exercised without needing to fake real file
+/// ownership or the process uid.
+#[cfg(unix)]
+fn evaluate_ancestors(current_uid: u32, ancestors: &[(PathBuf, u32, u32)]) -> Result<()> {
+    let mut seen_owned = false;
+    for (path, uid, mode) in ancestors {
+        if *uid != current_uid {
+            if seen_owned {
+                // Everything below this point already checked out; this is
+                // presumably a system directory (e.g. `/`, `/home`) above
+                // our own trusted tree, not something to reject.
+                break;
+            }
+            return Err(SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::UntrustedDirectory,
+                format!(
+                    "refusing to write: {} is not owned by the current user (set {} to override)",
+                    path.display(),
+                    TRUST_OVERRIDE_ENV_VAR
+                ),
+            ));
+        }
+        seen_owned = true;
+        if mode & 0o022 != 0 {
+            return Err(SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::UntrustedDirectory,
+                format!(
+                    "refusing to write: {} is writable by group or other (set {} to override)",
+                    path.display(),
+                    TRUST_OVERRIDE_ENV_VAR
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_trusted_inner(_absolute: &Path) -> Result<()> {
+    // Ownership and mode bits are a Unix-specific notion; nothing to check elsewhere.
+    Ok(())
+}
+
+/// Returns the real user ID of the current process.
+///
+/// Implemented as a direct libc call rather than pulling in a dependency
+/// for a single syscall.
+#[cfg(unix)]
+fn current_uid() -> u32 {
+    extern "C" {
+        fn getuid() -> u32;
+    }
+    unsafe { getuid() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_trusted_tempdir_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("secret.txt");
+        assert!(check_trusted(&target).is_ok());
+    }
+
+    #[test]
+    fn test_group_writable_directory_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut perms = fs::metadata(dir.path()).unwrap().permissions();
+        perms.set_mode(0o777);
+        fs::set_permissions(dir.path(), perms).unwrap();
+
+        let target = dir.path().join("secret.txt");
+        let err = check_trusted(&target).unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::UntrustedDirectory));
+    }
+
+    #[test]
+    fn test_override_env_var_skips_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut perms = fs::metadata(dir.path()).unwrap().permissions();
+        perms.set_mode(0o777);
+        fs::set_permissions(dir.path(), perms).unwrap();
+
+        let target = dir.path().join("secret.txt");
+        // SAFETY: test runs single-threaded with respect to this env var.
+        unsafe {
+            std::env::set_var(TRUST_OVERRIDE_ENV_VAR, "1");
+        }
+        let result = check_trusted(&target);
+        unsafe {
+            std::env::remove_var(TRUST_OVERRIDE_ENV_VAR);
+        }
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_nonexistent_parent_chain_is_not_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("does/not/exist/yet/secret.txt");
+        assert!(check_trusted(&target).is_ok());
+    }
+
+    #[test]
+    fn test_root_owned_ancestors_above_a_user_owned_home_are_trusted() {
+        // Regression test for a non-root user (uid 1000) writing under their
+        // own, correctly-owned home directory, with root-owned (uid 0)
+        // ancestors above it (as `/`, `/home`, etc. ordinarily are). This
+        // must succeed rather than rejecting every non-root user's home by
+        // default; constructed directly against `evaluate_ancestors` since
+        // the test suite itself may be running as root.
+        let ancestors = vec![
+            (PathBuf::from("/home/user/project"), 1000, 0o755),
+            (PathBuf::from("/home/user"), 1000, 0o755),
+            (PathBuf::from("/home"), 0, 0o755),
+            (PathBuf::from("/"), 0, 0o755),
+        ];
+        assert!(evaluate_ancestors(1000, &ancestors).is_ok());
+    }
+
+    #[test]
+    fn test_directory_not_owned_by_current_user_is_rejected_even_as_first_ancestor() {
+        let ancestors = vec![(PathBuf::from("/tmp/shared"), 0, 0o755)];
+        let err = evaluate_ancestors(1000, &ancestors).unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::UntrustedDirectory));
+    }
+
+    #[test]
+    fn test_group_writable_owned_directory_is_rejected_via_evaluate_ancestors() {
+        let ancestors = vec![(PathBuf::from("/home/user"), 1000, 0o775)];
+        let err = evaluate_ancestors(1000, &ancestors).unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::UntrustedDirectory));
+    }
+}
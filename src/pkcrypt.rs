@@ -0,0 +1,258 @@
+//! Public-key ("recipient") encryption mode
+//!
+//! Lets a file be sealed to someone's X25519 public key without a shared
+//! passphrase, using the same `crypto_box` construction as libsodium's
+//! `crypto_box_seal`: a fresh ephemeral X25519 keypair performs a
+//! Diffie-Hellman exchange with the recipient's public key, and the
+//! resulting shared secret keys an XSalsa20Poly1305 box. Only the
+//! recipient's secret key is needed to decrypt; the sender never needs to
+//! be contacted again.
+//!
+//! On-disk format (armored under `varmor::Version::Pk1`):
+//! - ephemeral public key: 32 bytes
+//! - nonce: 24 bytes
+//! - length: 8 bytes (big-endian signed int64)
+//! - sealed box: variable length
+
+use crate::error::{ErrorCategory, ErrorKind, Result, SaltyboxError};
+use crypto_box::aead::{Aead, AeadCore};
+use crypto_box::{PublicKey, SalsaBox, SecretKey};
+use rand::rngs::OsRng;
+use std::path::Path;
+
+/// Length of an X25519 public key in bytes
+const PUBLIC_KEY_LEN: usize = 32;
+
+/// Length of the XSalsa20Poly1305 nonce in bytes
+const NONCE_LEN: usize = 24;
+
+/// Encrypt `plaintext` to `recipient`'s X25519 public key.
+///
+/// Generates a fresh ephemeral keypair for this message; the sender's
+/// ephemeral secret key is discarded immediately after sealing and never
+/// needs to be retained or transmitted.
+pub fn encrypt_to_recipient(recipient: &PublicKey, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let ephemeral_secret = SecretKey::generate(&mut OsRng);
+    let ephemeral_public = ephemeral_secret.public_key();
+
+    let nonce = SalsaBox::generate_nonce(&mut OsRng);
+    let cipher = SalsaBox::new(recipient, &ephemeral_secret);
+    let sealed = cipher.encrypt(&nonce, plaintext).map_err(|e| {
+        SaltyboxError::with_kind(
+            ErrorCategory::Internal,
+            ErrorKind::SecretboxFailure,
+            format!("encryption failed: {}", e),
+        )
+    })?;
+
+    let sealed_len = sealed.len() as i64;
+    let mut out =
+        Vec::with_capacity(PUBLIC_KEY_LEN + NONCE_LEN + 8 + sealed.len());
+    out.extend_from_slice(ephemeral_public.as_bytes());
+    out.extend_from_slice(nonce.as_slice());
+    out.extend_from_slice(&sealed_len.to_be_bytes());
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Decrypt a message sealed with [`encrypt_to_recipient`] using our secret key.
+pub fn decrypt_with_key(secret: &SecretKey, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let mut pos = 0;
+
+    if ciphertext.len() < PUBLIC_KEY_LEN {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TruncatedInput,
+            "input likely truncated while reading ephemeral public key",
+        ));
+    }
+    let mut ephemeral_public_bytes = [0u8; PUBLIC_KEY_LEN];
+    ephemeral_public_bytes.copy_from_slice(&ciphertext[pos..pos + PUBLIC_KEY_LEN]);
+    let ephemeral_public = PublicKey::from(ephemeral_public_bytes);
+    pos += PUBLIC_KEY_LEN;
+
+    if ciphertext.len() < pos + NONCE_LEN {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TruncatedInput,
+            "input likely truncated while reading nonce",
+        ));
+    }
+    let nonce = crypto_box::Nonce::clone_from_slice(&ciphertext[pos..pos + NONCE_LEN]);
+    pos += NONCE_LEN;
+
+    if ciphertext.len() < pos + 8 {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TruncatedInput,
+            "input likely truncated while reading sealed box length",
+        ));
+    }
+    let sealed_len = i64::from_be_bytes(ciphertext[pos..pos + 8].try_into().unwrap());
+    pos += 8;
+
+    if sealed_len < 0 || sealed_len as usize > ciphertext.len().saturating_sub(pos) {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::BinaryFormat,
+            "sealed box length invalid or exceeds available input",
+        ));
+    }
+    let sealed_len = sealed_len as usize;
+    let sealed = &ciphertext[pos..pos + sealed_len];
+    pos += sealed_len;
+
+    if pos != ciphertext.len() {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TrailingData,
+            "unexpected data after sealed box",
+        ));
+    }
+
+    let cipher = SalsaBox::new(&ephemeral_public, secret);
+    cipher.decrypt(&nonce, sealed).map_err(|_| {
+        SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::AuthenticationFailed,
+            "corrupt input, tampered-with data, or wrong secret key",
+        )
+    })
+}
+
+/// Load an X25519 recipient public key from an OpenSSH-format public key
+/// file (`ssh-ed25519 AAAA...`), converting the Ed25519 point to its
+/// Montgomery (X25519) form.
+pub fn load_ssh_public_key(path: &Path) -> Result<PublicKey> {
+    let data = std::fs::read_to_string(path).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::User,
+            ErrorKind::Io,
+            format!("failed to read {}", path.display()),
+            e,
+        )
+    })?;
+    let key = ssh_key::PublicKey::from_openssh(&data).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::User,
+            ErrorKind::BinaryFormat,
+            "failed to parse OpenSSH public key",
+            e,
+        )
+    })?;
+    let ed25519 = key.key_data().ed25519().ok_or_else(|| {
+        SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::BinaryFormat,
+            "only ed25519 OpenSSH keys are supported",
+        )
+    })?;
+    ed25519_public_to_x25519(&ed25519.0)
+}
+
+/// Load an X25519 recipient secret key from an OpenSSH-format private key
+/// file, decrypting it with `passphrase` first if it is encrypted.
+pub fn load_ssh_secret_key(path: &Path, passphrase: Option<&[u8]>) -> Result<SecretKey> {
+    let data = std::fs::read_to_string(path).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::User,
+            ErrorKind::Io,
+            format!("failed to read {}", path.display()),
+            e,
+        )
+    })?;
+    let mut key = ssh_key::PrivateKey::from_openssh(&data).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::User,
+            ErrorKind::BinaryFormat,
+            "failed to parse OpenSSH private key",
+            e,
+        )
+    })?;
+    if key.is_encrypted() {
+        let passphrase = passphrase.ok_or_else(|| {
+            SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::PassphraseUnavailable,
+                "OpenSSH key is encrypted; a passphrase is required",
+            )
+        })?;
+        key = key.decrypt(passphrase).map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::User,
+                ErrorKind::AuthenticationFailed,
+                "failed to decrypt OpenSSH key; wrong passphrase?",
+                e,
+            )
+        })?;
+    }
+    let ssh_key::private::KeypairData::Ed25519(keypair) = key.key_data() else {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::BinaryFormat,
+            "only ed25519 OpenSSH keys are supported",
+        ));
+    };
+    ed25519_secret_to_x25519(&keypair.private.to_bytes())
+}
+
+fn ed25519_public_to_x25519(bytes: &[u8; 32]) -> Result<PublicKey> {
+    let point = curve25519_dalek::edwards::CompressedEdwardsY(*bytes)
+        .decompress()
+        .ok_or_else(|| {
+            SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::BinaryFormat,
+                "invalid ed25519 public key point",
+            )
+        })?;
+    Ok(PublicKey::from(point.to_montgomery().to_bytes()))
+}
+
+fn ed25519_secret_to_x25519(seed: &[u8; 32]) -> Result<SecretKey> {
+    use sha2::Digest;
+    let hash = sha2::Sha512::digest(seed);
+    let mut scalar = [0u8; 32];
+    scalar.copy_from_slice(&hash[..32]);
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+    Ok(SecretKey::from(scalar))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let secret = SecretKey::generate(&mut OsRng);
+        let public = secret.public_key();
+
+        let plaintext = b"hello, recipient";
+        let ciphertext = encrypt_to_recipient(&public, plaintext).unwrap();
+        let decrypted = decrypt_with_key(&secret, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let secret = SecretKey::generate(&mut OsRng);
+        let public = secret.public_key();
+        let other_secret = SecretKey::generate(&mut OsRng);
+
+        let ciphertext = encrypt_to_recipient(&public, b"secret").unwrap();
+        let result = decrypt_with_key(&other_secret, &ciphertext);
+
+        let err = result.expect_err("expected authentication failure");
+        assert_eq!(err.kind, Some(ErrorKind::AuthenticationFailed));
+    }
+
+    #[test]
+    fn test_truncated_ciphertext() {
+        let secret = SecretKey::generate(&mut OsRng);
+        let result = decrypt_with_key(&secret, &[1, 2, 3]);
+        let err = result.expect_err("expected truncated input error");
+        assert_eq!(err.kind, Some(ErrorKind::TruncatedInput));
+    }
+}
@@ -0,0 +1,287 @@
+//! Reusable KDF-parameter + passphrase-verifier profiles.
+//!
+//! `saltybox init` derives a [`Kdf`] once from a passphrase and persists it,
+//! alongside a verifier (not the derived key itself), to a profile file.
+//! Later `encrypt`/`decrypt`/`update` invocations can load that profile by
+//! name with `--profile` to reuse its KDF/cost settings and get a fast
+//! "wrong passphrase" check (see [`Profile::verify`]) before any real file
+//! crypto is attempted.
+//!
+//! The verifier is just the output of [`secretcrypt::encrypt_with_kdf`]
+//! sealing a fixed plaintext, so it's self-describing (see
+//! [`secretcrypt::kdf_of`]) and verifying it is exactly as hard as
+//! decrypting any other saltybox ciphertext - no separate KDF
+//! implementation to keep in sync.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::{ErrorCategory, ErrorKind, Result, SaltyboxError};
+use crate::pathtrust;
+use crate::secretcrypt::{self, Kdf};
+use crate::varmor;
+
+/// Environment variable overriding where profiles are stored; if unset,
+/// falls back to `$XDG_CONFIG_HOME/saltybox`, then `$HOME/.config/saltybox`.
+pub const CONFIG_DIR_ENV_VAR: &str = "SALTYBOX_CONFIG_DIR";
+
+/// Fixed plaintext sealed under a profile's passphrase to build its
+/// verifier. The exact bytes don't matter; only that decrypting the
+/// verifier with the right passphrase reproduces them.
+const VERIFIER_PLAINTEXT: &[u8] = b"saltybox-profile-verifier-v1";
+
+/// A saved profile: a [`Kdf`] and cost parameters, plus a verifier blob that
+/// lets a passphrase be checked against them without doing any real file
+/// encryption or decryption.
+pub struct Profile {
+    verifier: Vec<u8>,
+}
+
+impl Profile {
+    /// Derives a new profile from `passphrase` using `kdf`.
+    pub fn init(passphrase: &[u8], kdf: Kdf) -> Result<Self> {
+        let verifier = secretcrypt::encrypt_with_kdf(passphrase, VERIFIER_PLAINTEXT, kdf)
+            .map_err(|e| SaltyboxError::with_source(ErrorCategory::Internal, "failed to build profile verifier", e))?;
+        Ok(Self { verifier })
+    }
+
+    /// The KDF and cost parameters this profile was created with.
+    pub fn kdf(&self) -> Result<Kdf> {
+        secretcrypt::kdf_of(&self.verifier)
+            .map_err(|e| SaltyboxError::with_source(ErrorCategory::Internal, "profile has a corrupt KDF header", e))
+    }
+
+    /// Checks `passphrase` against this profile's stored verifier, giving a
+    /// fast, unambiguous "wrong passphrase" error before any real file
+    /// crypto is attempted.
+    pub fn verify(&self, passphrase: &[u8]) -> Result<()> {
+        secretcrypt::decrypt_with_kdf(passphrase, &self.verifier).map_err(|_| {
+            SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::AuthenticationFailed,
+                "passphrase does not match the saved profile",
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Writes this profile to `path` (mode 0o600 on Unix), armored the same
+    /// way any other `Kdf1` ciphertext would be.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        pathtrust::check_trusted(path)?;
+        let armored = varmor::wrap_version(&self.verifier, varmor::Version::Kdf1);
+        write_profile_file(path, armored.as_bytes())
+    }
+
+    /// Loads a profile previously written by [`Profile::save`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let armored_bytes = fs::read(path).map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::User,
+                ErrorKind::Io,
+                format!("failed to read profile {}", path.display()),
+                e,
+            )
+        })?;
+        let armored = String::from_utf8(armored_bytes).map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::User,
+                ErrorKind::Io,
+                "profile file is not valid UTF-8",
+                e,
+            )
+        })?;
+        let (version, verifier) =
+            varmor::unwrap_version(&armored).map_err(|e| e.with_context("failed to unarmor profile"))?;
+        if version != varmor::Version::Kdf1 {
+            return Err(SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::ArmoringInvalid,
+                "profile file is not in the expected format",
+            ));
+        }
+        Ok(Self { verifier })
+    }
+}
+
+fn write_profile_file(path: &Path, contents: &[u8]) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::fs::OpenOptions;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true).mode(0o600);
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        ))]
+        options.custom_flags(pathtrust::O_NOFOLLOW);
+
+        let mut file = options.open(path).map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::User,
+                ErrorKind::Io,
+                format!("failed to open {}", path.display()),
+                e,
+            )
+        })?;
+        file.write_all(contents).map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::Internal,
+                ErrorKind::Io,
+                format!("failed to write {}", path.display()),
+                e,
+            )
+        })?;
+        file.sync_all().map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::Internal,
+                ErrorKind::Io,
+                format!("failed to sync {}", path.display()),
+                e,
+            )
+        })
+    }
+
+    #[cfg(not(unix))]
+    {
+        let mut file = fs::File::create(path).map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::User,
+                ErrorKind::Io,
+                format!("failed to open {}", path.display()),
+                e,
+            )
+        })?;
+        file.write_all(contents).map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::Internal,
+                ErrorKind::Io,
+                format!("failed to write {}", path.display()),
+                e,
+            )
+        })?;
+        file.sync_all().map_err(|e| {
+            SaltyboxError::with_kind_and_source(
+                ErrorCategory::Internal,
+                ErrorKind::Io,
+                format!("failed to sync {}", path.display()),
+                e,
+            )
+        })
+    }
+}
+
+/// Directory profiles are stored in by default: [`CONFIG_DIR_ENV_VAR`] if
+/// set, else `$XDG_CONFIG_HOME/saltybox`, else `$HOME/.config/saltybox`.
+pub fn config_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV_VAR) {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Ok(PathBuf::from(dir).join("saltybox"));
+    }
+    let home = std::env::var_os("HOME").ok_or_else(|| {
+        SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::InvalidArgument,
+            format!(
+                "could not determine a config directory: set {}, $XDG_CONFIG_HOME, or $HOME",
+                CONFIG_DIR_ENV_VAR
+            ),
+        )
+    })?;
+    Ok(PathBuf::from(home).join(".config").join("saltybox"))
+}
+
+/// Creates [`config_dir`] (and any missing parents) if it doesn't already exist.
+pub fn ensure_config_dir() -> Result<PathBuf> {
+    let dir = config_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::Internal,
+            ErrorKind::Io,
+            format!("failed to create config directory {}", dir.display()),
+            e,
+        )
+    })?;
+    Ok(dir)
+}
+
+/// Path to the profile named `name` within [`config_dir`].
+pub fn profile_path(name: &str) -> Result<PathBuf> {
+    Ok(config_dir()?.join(format!("{name}.profile")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_save_load_verify_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("work.profile");
+
+        let profile = Profile::init(b"correct horse battery staple", Kdf::SCRYPT_DEFAULT).unwrap();
+        profile.save(&path).unwrap();
+
+        let loaded = Profile::load(&path).unwrap();
+        assert!(loaded.verify(b"correct horse battery staple").is_ok());
+        assert_eq!(loaded.kdf().unwrap(), Kdf::SCRYPT_DEFAULT);
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("work.profile");
+
+        let profile = Profile::init(b"right passphrase", Kdf::SCRYPT_DEFAULT).unwrap();
+        profile.save(&path).unwrap();
+
+        let loaded = Profile::load(&path).unwrap();
+        let err = loaded.verify(b"wrong passphrase").unwrap_err();
+        assert_eq!(err.kind, Some(ErrorKind::AuthenticationFailed));
+    }
+
+    #[test]
+    fn test_load_rejects_non_profile_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("not-a-profile.profile");
+        fs::write(&path, b"not a saltybox file at all").unwrap();
+
+        assert!(Profile::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_config_dir_honors_override_env_var() {
+        let dir = tempfile::tempdir().unwrap();
+        // SAFETY: test runs single-threaded with respect to this env var.
+        unsafe {
+            std::env::set_var(CONFIG_DIR_ENV_VAR, dir.path());
+        }
+        let result = config_dir();
+        unsafe {
+            std::env::remove_var(CONFIG_DIR_ENV_VAR);
+        }
+        assert_eq!(result.unwrap(), dir.path());
+    }
+
+    #[test]
+    fn test_profile_path_appends_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        // SAFETY: test runs single-threaded with respect to this env var.
+        unsafe {
+            std::env::set_var(CONFIG_DIR_ENV_VAR, dir.path());
+        }
+        let result = profile_path("work");
+        unsafe {
+            std::env::remove_var(CONFIG_DIR_ENV_VAR);
+        }
+        assert_eq!(result.unwrap(), dir.path().join("work.profile"));
+    }
+}
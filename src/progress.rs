@@ -0,0 +1,181 @@
+//! Progress reporting for long-running encrypt/decrypt operations
+//!
+//! The streaming fileops functions ([`file_ops::encrypt_file_stream`],
+//! [`file_ops::decrypt_file_stream`]) report bytes processed through the
+//! [`Progress`] trait as they go, so a caller can show a live progress bar
+//! for multi-gigabyte runs without the crypto/IO code needing to know
+//! anything about terminals. [`SilentProgress`] is a no-op, appropriate for
+//! piped/scripted usage or when stderr isn't a TTY; [`TerminalProgress`]
+//! renders an indicatif progress bar.
+//!
+//! [`file_ops::encrypt_file_stream`]: crate::file_ops::encrypt_file_stream
+//! [`file_ops::decrypt_file_stream`]: crate::file_ops::decrypt_file_stream
+
+use std::io::{Read, Write};
+
+/// Reports progress of a long-running byte-oriented operation.
+pub trait Progress {
+    /// Set (or clear) the total number of bytes expected, if known upfront.
+    fn set_total(&mut self, total: Option<u64>);
+    /// Report that `delta` more bytes have been processed.
+    fn inc(&mut self, delta: u64);
+    /// Mark the operation as complete.
+    fn finish(&mut self);
+}
+
+/// A [`Progress`] that does nothing; used for piped/scripted usage, where
+/// a progress bar would just be noise mixed into stderr.
+#[derive(Debug, Default)]
+pub struct SilentProgress;
+
+impl Progress for SilentProgress {
+    fn set_total(&mut self, _total: Option<u64>) {}
+    fn inc(&mut self, _delta: u64) {}
+    fn finish(&mut self) {}
+}
+
+/// A [`Progress`] backed by an interactive indicatif terminal progress bar.
+pub struct TerminalProgress {
+    bar: indicatif::ProgressBar,
+}
+
+impl TerminalProgress {
+    pub fn new() -> Self {
+        Self {
+            bar: indicatif::ProgressBar::new(0),
+        }
+    }
+
+    fn style_for(total: Option<u64>) -> indicatif::ProgressStyle {
+        let template = if total.is_some() {
+            "{bar:40.cyan/blue} {bytes}/{total_bytes} ({bytes_per_sec}, eta {eta})"
+        } else {
+            "{spinner} {bytes} processed ({bytes_per_sec})"
+        };
+        indicatif::ProgressStyle::with_template(template)
+            .expect("progress bar template is valid")
+    }
+}
+
+impl Default for TerminalProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Progress for TerminalProgress {
+    fn set_total(&mut self, total: Option<u64>) {
+        self.bar.set_style(Self::style_for(total));
+        self.bar.set_length(total.unwrap_or(0));
+    }
+
+    fn inc(&mut self, delta: u64) {
+        self.bar.inc(delta);
+    }
+
+    fn finish(&mut self) {
+        self.bar.finish();
+    }
+}
+
+/// Wraps a [`Read`], reporting every successful read through a [`Progress`].
+pub struct ProgressReader<'a, R> {
+    inner: R,
+    progress: &'a mut dyn Progress,
+}
+
+impl<'a, R: Read> ProgressReader<'a, R> {
+    pub fn new(inner: R, progress: &'a mut dyn Progress) -> Self {
+        Self { inner, progress }
+    }
+}
+
+impl<'a, R: Read> Read for ProgressReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.progress.inc(n as u64);
+        Ok(n)
+    }
+}
+
+/// Wraps a [`Write`], reporting every successful write through a [`Progress`].
+pub struct ProgressWriter<'a, W> {
+    inner: W,
+    progress: &'a mut dyn Progress,
+}
+
+impl<'a, W: Write> ProgressWriter<'a, W> {
+    pub fn new(inner: W, progress: &'a mut dyn Progress) -> Self {
+        Self { inner, progress }
+    }
+}
+
+impl<'a, W: Write> Write for ProgressWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.progress.inc(n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingProgress {
+        total: Option<u64>,
+        processed: u64,
+        finished: bool,
+    }
+
+    impl Progress for RecordingProgress {
+        fn set_total(&mut self, total: Option<u64>) {
+            self.total = total;
+        }
+        fn inc(&mut self, delta: u64) {
+            self.processed += delta;
+        }
+        fn finish(&mut self) {
+            self.finished = true;
+        }
+    }
+
+    #[test]
+    fn test_progress_reader_reports_bytes_read() {
+        let mut progress = RecordingProgress::default();
+        let data = b"hello, progress!".to_vec();
+        let mut reader = ProgressReader::new(data.as_slice(), &mut progress);
+
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+
+        assert_eq!(buf, data);
+        assert_eq!(progress.processed, data.len() as u64);
+    }
+
+    #[test]
+    fn test_progress_writer_reports_bytes_written() {
+        let mut progress = RecordingProgress::default();
+        let mut output = Vec::new();
+        {
+            let mut writer = ProgressWriter::new(&mut output, &mut progress);
+            writer.write_all(b"written data").unwrap();
+        }
+
+        assert_eq!(output, b"written data");
+        assert_eq!(progress.processed, "written data".len() as u64);
+    }
+
+    #[test]
+    fn test_silent_progress_is_inert() {
+        let mut progress = SilentProgress;
+        progress.set_total(Some(100));
+        progress.inc(50);
+        progress.finish();
+    }
+}
@@ -11,11 +11,14 @@
 //! - sealed box: variable length (includes 16-byte Poly1305 MAC)
 
 use anyhow::{Context, Result, bail};
+use argon2::{Algorithm as Argon2Algorithm, Argon2, Params as Argon2Params, Version as Argon2Version};
+use crate::error::{ErrorCategory, ErrorKind, SaltyboxError};
 use crypto_secretbox::aead::{Aead, KeyInit};
 use crypto_secretbox::{Nonce, XSalsa20Poly1305};
 use rand::RngCore;
 use rand::rngs::OsRng;
 use scrypt::{Params, scrypt};
+use std::io::{Read, Write};
 use std::mem::{size_of, size_of_val};
 
 /// Length of salt in bytes
@@ -27,6 +30,20 @@ const NONCE_LEN: usize = 24;
 /// Length of derived key in bytes
 const KEY_LEN: usize = 32;
 
+/// Plaintext chunk size used by the streaming STREAM construction
+/// (see [`encrypt_stream`]/[`decrypt_stream`]).
+pub const STREAM_CHUNK_LEN: usize = 64 * 1024;
+
+/// Length of the random per-stream nonce prefix; the remaining 5 bytes of
+/// the 24-byte nonce are a big-endian chunk counter followed by a
+/// last-chunk flag.
+const STREAM_NONCE_PREFIX_LEN: usize = 19;
+
+/// Largest sealed chunk we're willing to allocate for when decrypting a
+/// stream, guarding against a malicious/corrupt length prefix causing an
+/// unbounded allocation.
+const STREAM_MAX_SEALED_CHUNK_LEN: usize = STREAM_CHUNK_LEN + 1024;
+
 /// scrypt N parameter (CPU/memory cost)
 const SCRYPT_N: u32 = 32768;
 
@@ -37,17 +54,31 @@ const SCRYPT_R: u32 = 8;
 const SCRYPT_P: u32 = 1;
 
 /// Derive a 32-byte key from a passphrase and salt using scrypt
-fn derive_key(passphrase: &[u8], salt: &[u8; SALT_LEN]) -> Result<[u8; KEY_LEN]> {
+fn derive_key(passphrase: &[u8], salt: &[u8; SALT_LEN]) -> crate::Result<[u8; KEY_LEN]> {
     let params = Params::new(
         (SCRYPT_N as f64).log2() as u8, // log_n
         SCRYPT_R,
         SCRYPT_P,
         KEY_LEN,
     )
-    .context("failed to create scrypt params")?;
+    .map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::Internal,
+            ErrorKind::ScryptFailure,
+            "failed to create scrypt params",
+            e,
+        )
+    })?;
 
     let mut key = [0u8; KEY_LEN];
-    scrypt(passphrase, salt, &params, &mut key).context("scrypt key derivation failed")?;
+    scrypt(passphrase, salt, &params, &mut key).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::Internal,
+            ErrorKind::ScryptFailure,
+            "scrypt key derivation failed",
+            e,
+        )
+    })?;
 
     Ok(key)
 }
@@ -55,7 +86,7 @@ fn derive_key(passphrase: &[u8], salt: &[u8; SALT_LEN]) -> Result<[u8; KEY_LEN]>
 /// Encrypt plaintext with a passphrase using random salt and nonce
 ///
 /// Returns the binary format: salt(8) + nonce(24) + length(8) + sealedbox(variable)
-pub fn encrypt(passphrase: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+pub fn encrypt(passphrase: &[u8], plaintext: &[u8]) -> crate::Result<Vec<u8>> {
     let mut salt = [0u8; SALT_LEN];
     OsRng.fill_bytes(&mut salt);
 
@@ -74,15 +105,19 @@ pub fn encrypt_deterministic(
     plaintext: &[u8],
     salt: &[u8; SALT_LEN],
     nonce: &[u8; NONCE_LEN],
-) -> Result<Vec<u8>> {
+) -> crate::Result<Vec<u8>> {
     let key = derive_key(passphrase, salt)?;
 
     let cipher = XSalsa20Poly1305::new(&key.into());
 
     let nonce_obj = Nonce::from(*nonce);
-    let sealed_box = cipher
-        .encrypt(&nonce_obj, plaintext)
-        .map_err(|e| anyhow::anyhow!("encryption failed: {}", e))?;
+    let sealed_box = cipher.encrypt(&nonce_obj, plaintext).map_err(|e| {
+        SaltyboxError::with_kind(
+            ErrorCategory::Internal,
+            ErrorKind::SecretboxFailure,
+            format!("encryption failed: {}", e),
+        )
+    })?;
 
     let sealed_box_len = sealed_box.len() as i64;
     let mut output =
@@ -95,71 +130,1128 @@ pub fn encrypt_deterministic(
     Ok(output)
 }
 
-/// Decrypt ciphertext with a passphrase
-pub fn decrypt(passphrase: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+/// A parsed `salt(8)+nonce(24)+length(8)+sealedbox` tail, shared by
+/// [`decrypt`] and [`decrypt_with_params`] (which only differ in what
+/// precedes this tail and how the key is derived from the salt).
+struct SealedBox<'a> {
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+    sealed_box: &'a [u8],
+}
+
+fn parse_sealed_box(ciphertext: &[u8]) -> crate::Result<SealedBox<'_>> {
     let mut pos = 0;
 
     if ciphertext.len() < pos + SALT_LEN {
-        bail!("input likely truncated while reading salt");
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TruncatedInput,
+            "input likely truncated while reading salt",
+        ));
     }
-    let salt: [u8; SALT_LEN] = ciphertext[pos..pos + SALT_LEN]
-        .try_into()
-        .context("failed to read salt")?;
+    let salt: [u8; SALT_LEN] = ciphertext[pos..pos + SALT_LEN].try_into().unwrap();
     pos += SALT_LEN;
 
     if ciphertext.len() < pos + NONCE_LEN {
-        bail!("input likely truncated while reading nonce");
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TruncatedInput,
+            "input likely truncated while reading nonce",
+        ));
     }
-    let nonce: [u8; NONCE_LEN] = ciphertext[pos..pos + NONCE_LEN]
-        .try_into()
-        .context("failed to read nonce")?;
+    let nonce: [u8; NONCE_LEN] = ciphertext[pos..pos + NONCE_LEN].try_into().unwrap();
     pos += NONCE_LEN;
 
     if ciphertext.len() < pos + size_of::<i64>() {
-        bail!("input likely truncated while reading sealed box");
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TruncatedInput,
+            "input likely truncated while reading sealed box",
+        ));
     }
     let length_bytes: [u8; 8] = ciphertext[pos..pos + size_of::<i64>()]
         .try_into()
-        .context("failed to read length")?;
+        .unwrap();
     let sealed_box_len = i64::from_be_bytes(length_bytes);
     pos += size_of::<i64>();
 
     if sealed_box_len < 0 {
-        bail!("negative sealed box length (when interpreted as a big-endian i64)");
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::BinaryFormat,
+            "negative sealed box length (when interpreted as a big-endian i64)",
+        ));
     }
 
     // Check if length exceeds platform's maximum isize. *Valid* input
     // can fail this check if the platforms' isize is small.
     if sealed_box_len > isize::MAX as i64 {
-        bail!("sealed box length exceeds this system's max isize");
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::BinaryFormat,
+            "sealed box length exceeds this system's max isize",
+        ));
     }
 
     let sealed_box_len = sealed_box_len as usize;
 
     if sealed_box_len > ciphertext.len() {
-        bail!("truncated or corrupt input; claimed length greater than available input");
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TruncatedInput,
+            "truncated or corrupt input; claimed length greater than available input",
+        ));
     }
 
     if ciphertext.len() < pos + sealed_box_len {
-        bail!("truncated or corrupt input (while reading sealed box)");
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TruncatedInput,
+            "truncated or corrupt input (while reading sealed box)",
+        ));
     }
     let sealed_box = &ciphertext[pos..pos + sealed_box_len];
     pos += sealed_box_len;
 
     if pos < ciphertext.len() {
-        bail!("invalid input: unexpected data after sealed box");
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::TrailingData,
+            "invalid input: unexpected data after sealed box",
+        ));
     }
 
-    let key = derive_key(passphrase, &salt)?;
+    Ok(SealedBox {
+        salt,
+        nonce,
+        sealed_box,
+    })
+}
+
+/// Decrypt ciphertext with a passphrase
+pub fn decrypt(passphrase: &[u8], ciphertext: &[u8]) -> crate::Result<Vec<u8>> {
+    let parsed = parse_sealed_box(ciphertext)?;
+
+    let key = derive_key(passphrase, &parsed.salt)?;
+    let cipher = XSalsa20Poly1305::new(&key.into());
+    let nonce_obj = Nonce::from(parsed.nonce);
+    let plaintext = cipher.decrypt(&nonce_obj, parsed.sealed_box).map_err(|_| {
+        SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::AuthenticationFailed,
+            "corrupt input, tampered-with data, or bad passphrase",
+        )
+    })?;
+
+    Ok(plaintext)
+}
+
+/// Tunable scrypt cost parameters, self-describing in the ciphertext header
+/// produced by [`encrypt_with_params`] so the KDF cost can be raised over
+/// time without breaking old files (`decrypt_with_params` reads the
+/// parameters that were actually used, rather than assuming the hardcoded
+/// constants that back the original [`encrypt`]/[`decrypt`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    /// log2(N): scrypt's CPU/memory cost parameter
+    pub log2_n: u8,
+    /// scrypt's block size parameter
+    pub r: u8,
+    /// scrypt's parallelization parameter
+    pub p: u8,
+}
+
+/// Largest amount of memory (in bytes) we're willing to let a caller-chosen
+/// `ScryptParams` request scrypt allocate, roughly `128 * N * r`. Guards
+/// against absurd settings (accidental or malicious) causing an OOM.
+const SCRYPT_MAX_MEMORY_BYTES: u64 = 1 << 30; // 1 GiB
+
+impl ScryptParams {
+    /// The same cost the original, non-tunable [`encrypt`] has always used.
+    pub const INTERACTIVE: ScryptParams = ScryptParams {
+        log2_n: 15, // N = 32768
+        r: 8,
+        p: 1,
+    };
+
+    /// Reject parameter values that are nonsensical regardless of the
+    /// memory ceiling (zero is never a valid cost for any of N/r/p).
+    fn validate_basic(&self) -> Result<()> {
+        if self.log2_n == 0 {
+            bail!("scrypt log2(N) must be at least 1");
+        }
+        if self.r == 0 {
+            bail!("scrypt r must be at least 1");
+        }
+        if self.p == 0 {
+            bail!("scrypt p must be at least 1");
+        }
+        Ok(())
+    }
+
+    /// Reject settings that are nonsensical or would risk OOMing the
+    /// machine performing key derivation. This is the check `decrypt_with_params`
+    /// always applies to header-supplied parameters, since a malicious or
+    /// corrupt header must never be able to force an absurd N.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_basic()?;
+        let approx_memory_bytes = 128u64 * (1u64 << self.log2_n) * self.r as u64;
+        if approx_memory_bytes > SCRYPT_MAX_MEMORY_BYTES {
+            bail!(
+                "scrypt parameters would need ~{} bytes of memory, exceeding the {} byte limit",
+                approx_memory_bytes,
+                SCRYPT_MAX_MEMORY_BYTES
+            );
+        }
+        Ok(())
+    }
+
+    /// Like [`validate`](Self::validate), but skips the memory ceiling for
+    /// a caller who has explicitly opted in to costlier-than-default
+    /// parameters. Only appropriate when the caller chose the parameters
+    /// themselves; never applied to parameters read back out of a
+    /// ciphertext header.
+    pub fn validate_allow_expensive(&self) -> Result<()> {
+        self.validate_basic()
+    }
+}
+
+impl Default for ScryptParams {
+    fn default() -> Self {
+        Self::INTERACTIVE
+    }
+}
+
+/// Named scrypt cost presets for callers of [`encrypt_with_options`] who want
+/// a stronger key derivation for sensitive data without picking raw N/r/p
+/// values themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptOptions {
+    pub params: ScryptParams,
+}
+
+impl EncryptOptions {
+    /// The same cost [`ScryptParams::INTERACTIVE`] uses; appropriate for most data.
+    pub const INTERACTIVE: EncryptOptions = EncryptOptions {
+        params: ScryptParams::INTERACTIVE,
+    };
+
+    /// A higher cost appropriate for data worth protecting against a
+    /// long-running offline attack, at the expense of slower
+    /// encryption/decryption and ~32x the memory of `INTERACTIVE`.
+    pub const SENSITIVE: EncryptOptions = EncryptOptions {
+        params: ScryptParams {
+            log2_n: 20, // N = 1048576
+            r: 8,
+            p: 1,
+        },
+    };
+
+    /// Build options around an explicit, caller-chosen [`ScryptParams`].
+    pub fn with_params(params: ScryptParams) -> Self {
+        Self { params }
+    }
+}
+
+impl Default for EncryptOptions {
+    fn default() -> Self {
+        Self::INTERACTIVE
+    }
+}
+
+fn derive_key_with_params(
+    passphrase: &[u8],
+    salt: &[u8; SALT_LEN],
+    params: &ScryptParams,
+) -> Result<[u8; KEY_LEN]> {
+    let scrypt_params = Params::new(params.log2_n, params.r as u32, params.p as u32, KEY_LEN)
+        .context("failed to create scrypt params")?;
+
+    let mut key = [0u8; KEY_LEN];
+    scrypt(passphrase, salt, &scrypt_params, &mut key).context("scrypt key derivation failed")?;
+
+    Ok(key)
+}
+
+/// Encrypt plaintext with a passphrase, using tunable scrypt cost
+/// `params` instead of the hardcoded constants `encrypt` uses.
+///
+/// `allow_expensive` opts out of the memory-ceiling check in
+/// [`ScryptParams::validate`] for a caller who has deliberately chosen a
+/// cost above the conservative default limit; pass `false` unless the
+/// caller has surfaced that choice explicitly (e.g. a CLI flag).
+///
+/// Returns the binary format: `params(3) + salt(8) + nonce(24) + length(8)
+/// + sealedbox(variable)`.
+pub fn encrypt_with_params(
+    passphrase: &[u8],
+    plaintext: &[u8],
+    params: ScryptParams,
+    allow_expensive: bool,
+) -> Result<Vec<u8>> {
+    if allow_expensive {
+        params.validate_allow_expensive()?;
+    } else {
+        params.validate()?;
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key_with_params(passphrase, &salt, &params)?;
     let cipher = XSalsa20Poly1305::new(&key.into());
     let nonce_obj = Nonce::from(nonce);
+    let sealed_box = cipher
+        .encrypt(&nonce_obj, plaintext)
+        .map_err(|e| anyhow::anyhow!("encryption failed: {}", e))?;
+
+    let sealed_box_len = sealed_box.len() as i64;
+    let mut output = Vec::with_capacity(
+        3 + SALT_LEN + NONCE_LEN + size_of_val(&sealed_box_len) + sealed_box.len(),
+    );
+    output.push(params.log2_n);
+    output.push(params.r);
+    output.push(params.p);
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&sealed_box_len.to_be_bytes());
+    output.extend_from_slice(&sealed_box);
+
+    Ok(output)
+}
+
+/// Encrypt plaintext with a passphrase, using the scrypt cost selected by
+/// `options` (see [`EncryptOptions::SENSITIVE`] for stronger-than-default
+/// protection). Produces the same self-describing format as
+/// [`encrypt_with_params`], which this delegates to.
+pub fn encrypt_with_options(
+    passphrase: &[u8],
+    plaintext: &[u8],
+    options: EncryptOptions,
+) -> Result<Vec<u8>> {
+    encrypt_with_params(passphrase, plaintext, options.params, false)
+}
+
+/// Decrypt ciphertext produced by [`encrypt_with_params`], deriving the key
+/// with whichever scrypt cost parameters are recorded in its header.
+pub fn decrypt_with_params(passphrase: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    if ciphertext.len() < 3 {
+        bail!("input likely truncated while reading scrypt parameters");
+    }
+    let params = ScryptParams {
+        log2_n: ciphertext[0],
+        r: ciphertext[1],
+        p: ciphertext[2],
+    };
+    params
+        .validate()
+        .context("ciphertext header declares invalid or excessive scrypt parameters")?;
+
+    let parsed = parse_sealed_box(&ciphertext[3..])?;
+    let key = derive_key_with_params(passphrase, &parsed.salt, &params)?;
+    let cipher = XSalsa20Poly1305::new(&key.into());
+    let nonce_obj = Nonce::from(parsed.nonce);
     let plaintext = cipher
-        .decrypt(&nonce_obj, sealed_box)
+        .decrypt(&nonce_obj, parsed.sealed_box)
         .map_err(|_| anyhow::anyhow!("corrupt input, tampered-with data, or bad passphrase"))?;
 
     Ok(plaintext)
 }
 
+/// Key-derivation function selection for [`encrypt_with_kdf`]/[`decrypt_with_kdf`],
+/// tagged by a one-byte discriminant written into the ciphertext header so
+/// decryption always dispatches to the routine that was actually used to
+/// derive the key, independent of whatever the current default is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    /// scrypt, the KDF the original [`encrypt`]/[`decrypt`] have always used.
+    Scrypt { log_n: u8, r: u8, p: u8 },
+    /// Argon2id, the OWASP-recommended memory-hard password KDF.
+    Argon2id { m_cost: u32, t_cost: u32, p_cost: u32 },
+}
+
+/// One-byte discriminants for [`Kdf`], stored immediately after the magic
+/// marker in the `Kdf1` container format.
+const KDF_DISCRIMINANT_SCRYPT: u8 = 0;
+const KDF_DISCRIMINANT_ARGON2ID: u8 = 1;
+
+impl Kdf {
+    /// Same cost as [`ScryptParams::INTERACTIVE`]; the default KDF.
+    pub const SCRYPT_DEFAULT: Kdf = Kdf::Scrypt {
+        log_n: 15,
+        r: 8,
+        p: 1,
+    };
+
+    /// OWASP-recommended minimum Argon2id parameters (19 MiB, 2 iterations,
+    /// 1 degree of parallelism) as of this writing.
+    pub const ARGON2ID_DEFAULT: Kdf = Kdf::Argon2id {
+        m_cost: 19 * 1024,
+        t_cost: 2,
+        p_cost: 1,
+    };
+
+    fn discriminant(&self) -> u8 {
+        match self {
+            Kdf::Scrypt { .. } => KDF_DISCRIMINANT_SCRYPT,
+            Kdf::Argon2id { .. } => KDF_DISCRIMINANT_ARGON2ID,
+        }
+    }
+
+    /// Encodes this KDF's parameters (not including the discriminant byte).
+    fn encode_params(&self) -> Vec<u8> {
+        match self {
+            Kdf::Scrypt { log_n, r, p } => vec![*log_n, *r, *p],
+            Kdf::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                let mut out = Vec::with_capacity(12);
+                out.extend_from_slice(&m_cost.to_be_bytes());
+                out.extend_from_slice(&t_cost.to_be_bytes());
+                out.extend_from_slice(&p_cost.to_be_bytes());
+                out
+            }
+        }
+    }
+
+    /// Decodes a `Kdf` from a discriminant byte and the parameter bytes that
+    /// follow it, returning the parsed value and how many parameter bytes it
+    /// consumed.
+    fn decode(discriminant: u8, bytes: &[u8]) -> Result<(Kdf, usize)> {
+        match discriminant {
+            KDF_DISCRIMINANT_SCRYPT => {
+                if bytes.len() < 3 {
+                    bail!("input likely truncated while reading scrypt parameters");
+                }
+                Ok((
+                    Kdf::Scrypt {
+                        log_n: bytes[0],
+                        r: bytes[1],
+                        p: bytes[2],
+                    },
+                    3,
+                ))
+            }
+            KDF_DISCRIMINANT_ARGON2ID => {
+                if bytes.len() < 12 {
+                    bail!("input likely truncated while reading argon2id parameters");
+                }
+                let m_cost = u32::from_be_bytes(bytes[0..4].try_into().unwrap());
+                let t_cost = u32::from_be_bytes(bytes[4..8].try_into().unwrap());
+                let p_cost = u32::from_be_bytes(bytes[8..12].try_into().unwrap());
+                Ok((
+                    Kdf::Argon2id {
+                        m_cost,
+                        t_cost,
+                        p_cost,
+                    },
+                    12,
+                ))
+            }
+            other => bail!("unknown KDF discriminant {}", other),
+        }
+    }
+
+    /// Reject settings that are nonsensical or would risk OOMing the
+    /// machine performing key derivation.
+    fn validate(&self) -> Result<()> {
+        match self {
+            Kdf::Scrypt { log_n, r, p } => ScryptParams {
+                log2_n: *log_n,
+                r: *r,
+                p: *p,
+            }
+            .validate(),
+            Kdf::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                if *t_cost == 0 || *p_cost == 0 {
+                    bail!("argon2id t_cost and p_cost must be at least 1");
+                }
+                let approx_memory_bytes = (*m_cost as u64) * 1024;
+                if approx_memory_bytes > SCRYPT_MAX_MEMORY_BYTES {
+                    bail!(
+                        "argon2id m_cost would need ~{} bytes of memory, exceeding the {} byte limit",
+                        approx_memory_bytes,
+                        SCRYPT_MAX_MEMORY_BYTES
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Self::SCRYPT_DEFAULT
+    }
+}
+
+fn derive_key_with_kdf(passphrase: &[u8], salt: &[u8; SALT_LEN], kdf: &Kdf) -> Result<[u8; KEY_LEN]> {
+    match kdf {
+        Kdf::Scrypt { log_n, r, p } => derive_key_with_params(
+            passphrase,
+            salt,
+            &ScryptParams {
+                log2_n: *log_n,
+                r: *r,
+                p: *p,
+            },
+        ),
+        Kdf::Argon2id {
+            m_cost,
+            t_cost,
+            p_cost,
+        } => {
+            let params = Argon2Params::new(*m_cost, *t_cost, *p_cost, Some(KEY_LEN))
+                .map_err(|e| anyhow::anyhow!("invalid argon2id parameters: {}", e))?;
+            let argon2 = Argon2::new(Argon2Algorithm::Argon2id, Argon2Version::V0x13, params);
+            let mut key = [0u8; KEY_LEN];
+            argon2
+                .hash_password_into(passphrase, salt, &mut key)
+                .map_err(|e| anyhow::anyhow!("argon2id key derivation failed: {}", e))?;
+            Ok(key)
+        }
+    }
+}
+
+/// Encrypt plaintext with a passphrase, deriving the key with `kdf` instead
+/// of the hardcoded scrypt constants `encrypt` uses. Defaults to
+/// [`Kdf::SCRYPT_DEFAULT`] for backward compatibility; pass
+/// [`Kdf::ARGON2ID_DEFAULT`] (or custom parameters) to use Argon2id instead.
+///
+/// Returns the binary format: `kdf_discriminant(1) + kdf_params(variable) +
+/// salt(8) + nonce(24) + length(8) + sealedbox(variable)`.
+pub fn encrypt_with_kdf(passphrase: &[u8], plaintext: &[u8], kdf: Kdf) -> Result<Vec<u8>> {
+    kdf.validate()?;
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key_with_kdf(passphrase, &salt, &kdf)?;
+    let cipher = XSalsa20Poly1305::new(&key.into());
+    let nonce_obj = Nonce::from(nonce);
+    let sealed_box = cipher
+        .encrypt(&nonce_obj, plaintext)
+        .map_err(|e| anyhow::anyhow!("encryption failed: {}", e))?;
+
+    let sealed_box_len = sealed_box.len() as i64;
+    let params_bytes = kdf.encode_params();
+    let mut output = Vec::with_capacity(
+        1 + params_bytes.len() + SALT_LEN + NONCE_LEN + size_of_val(&sealed_box_len) + sealed_box.len(),
+    );
+    output.push(kdf.discriminant());
+    output.extend_from_slice(&params_bytes);
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&sealed_box_len.to_be_bytes());
+    output.extend_from_slice(&sealed_box);
+
+    Ok(output)
+}
+
+/// Decrypt ciphertext produced by [`encrypt_with_kdf`], deriving the key
+/// with whichever KDF and parameters are recorded in its header.
+pub fn decrypt_with_kdf(passphrase: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    if ciphertext.is_empty() {
+        bail!("input likely truncated while reading KDF discriminant");
+    }
+    let (kdf, params_len) = Kdf::decode(ciphertext[0], &ciphertext[1..])?;
+    kdf.validate()
+        .context("ciphertext header declares invalid or excessive KDF parameters")?;
+
+    let parsed = parse_sealed_box(&ciphertext[1 + params_len..])?;
+    let key = derive_key_with_kdf(passphrase, &parsed.salt, &kdf)?;
+    let cipher = XSalsa20Poly1305::new(&key.into());
+    let nonce_obj = Nonce::from(parsed.nonce);
+    let plaintext = cipher
+        .decrypt(&nonce_obj, parsed.sealed_box)
+        .map_err(|_| anyhow::anyhow!("corrupt input, tampered-with data, or bad passphrase"))?;
+
+    Ok(plaintext)
+}
+
+/// Reads back the [`Kdf`] recorded in the header of ciphertext produced by
+/// [`encrypt_with_kdf`], without deriving a key or attempting to decrypt
+/// anything. Useful for callers (e.g. `profile`) that want to know or
+/// display which KDF and cost a stored ciphertext uses.
+pub fn kdf_of(ciphertext: &[u8]) -> Result<Kdf> {
+    if ciphertext.is_empty() {
+        bail!("input likely truncated while reading KDF discriminant");
+    }
+    let (kdf, _params_len) = Kdf::decode(ciphertext[0], &ciphertext[1..])?;
+    Ok(kdf)
+}
+
+/// Deterministic variant of [`encrypt_with_kdf`] that uses caller-supplied
+/// salt and nonce instead of generating them randomly. Exists only so golden
+/// test vectors can pin exact byte output for each supported KDF; real
+/// callers should use [`encrypt_with_kdf`] so the salt and nonce are random.
+pub fn encrypt_deterministic_with_kdf(
+    passphrase: &[u8],
+    plaintext: &[u8],
+    kdf: Kdf,
+    salt: &[u8; SALT_LEN],
+    nonce: &[u8; NONCE_LEN],
+) -> Result<Vec<u8>> {
+    kdf.validate()?;
+
+    let key = derive_key_with_kdf(passphrase, salt, &kdf)?;
+    let cipher = XSalsa20Poly1305::new(&key.into());
+    let nonce_obj = Nonce::from(*nonce);
+    let sealed_box = cipher
+        .encrypt(&nonce_obj, plaintext)
+        .map_err(|e| anyhow::anyhow!("encryption failed: {}", e))?;
+
+    let sealed_box_len = sealed_box.len() as i64;
+    let params_bytes = kdf.encode_params();
+    let mut output = Vec::with_capacity(
+        1 + params_bytes.len() + SALT_LEN + NONCE_LEN + size_of_val(&sealed_box_len) + sealed_box.len(),
+    );
+    output.push(kdf.discriminant());
+    output.extend_from_slice(&params_bytes);
+    output.extend_from_slice(salt);
+    output.extend_from_slice(nonce);
+    output.extend_from_slice(&sealed_box_len.to_be_bytes());
+    output.extend_from_slice(&sealed_box);
+
+    Ok(output)
+}
+
+/// Which (if any) compression was applied to the plaintext before sealing,
+/// identified by a single unencrypted byte prepended to the output of
+/// [`encrypt`]. See [`encrypt_with_compression`]/[`decrypt_with_compression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Plaintext is sealed as-is; this is the identifier used for
+    /// backward compatibility with data that predates compression support.
+    None = 0,
+    /// Plaintext was deflated with zstd before sealing.
+    Zstd = 1,
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Zstd),
+            other => bail!("unsupported compression algorithm identifier: {}", other),
+        }
+    }
+}
+
+/// Encrypt plaintext with a passphrase, optionally compressing it first.
+///
+/// The output is a one-byte [`Compression`] identifier followed by the
+/// usual `encrypt` output (salt/nonce/length/sealedbox), so old
+/// (uncompressed) ciphertext and this format are never mistaken for each
+/// other as long as the caller keeps track of which one it produced (see
+/// `varmor::Version::Compressed1`, which `file_ops` uses for exactly that).
+pub fn encrypt_with_compression(
+    passphrase: &[u8],
+    plaintext: &[u8],
+    compression: Compression,
+) -> Result<Vec<u8>> {
+    let payload = match compression {
+        Compression::None => plaintext.to_vec(),
+        Compression::Zstd => {
+            zstd::stream::encode_all(plaintext, 0).context("zstd compression failed")?
+        }
+    };
+
+    let sealed = encrypt(passphrase, &payload)?;
+    let mut out = Vec::with_capacity(1 + sealed.len());
+    out.push(compression as u8);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Decrypt ciphertext produced by [`encrypt_with_compression`], inflating
+/// the plaintext if the header says it was compressed.
+pub fn decrypt_with_compression(passphrase: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let (&algo_byte, rest) = ciphertext
+        .split_first()
+        .context("input likely truncated while reading compression header")?;
+    let compression = Compression::try_from(algo_byte)?;
+    let payload = decrypt(passphrase, rest)?;
+    match compression {
+        Compression::None => Ok(payload),
+        Compression::Zstd => {
+            zstd::stream::decode_all(payload.as_slice()).context("zstd decompression failed")
+        }
+    }
+}
+
+/// Number of bytes occupied by a wrapped file key: the raw 32-byte key plus
+/// the 16-byte Poly1305 MAC added when it is sealed under a recipient's key.
+const WRAPPED_KEY_LEN: usize = KEY_LEN + 16;
+
+/// Per-recipient key wrap: a passphrase-derived salt/nonce plus the file key
+/// sealed under that passphrase's derived key.
+const RECIPIENT_WRAP_LEN: usize = SALT_LEN + NONCE_LEN + WRAPPED_KEY_LEN;
+
+/// Encrypt `plaintext` so that any one of `passphrases` can later decrypt it
+/// with [`decrypt_multi`].
+///
+/// A random file key is generated once and sealed separately for each
+/// recipient passphrase, so recipients never learn each other's passphrases
+/// and adding/removing a recipient (see [`add_recipient_multi`]/
+/// [`remove_recipient_multi`]) never requires re-encrypting the plaintext.
+///
+/// Returns the binary format:
+/// - recipient count: 4 bytes (big-endian u32)
+/// - one `RECIPIENT_WRAP_LEN`-byte wrap per recipient: `salt(8) + nonce(24)
+///   + wrapped_key(48)`
+/// - nonce: 24 bytes (for the payload, sealed under the file key)
+/// - length: 8 bytes (big-endian signed int64)
+/// - sealed box: variable length (the plaintext, sealed under the file key)
+pub fn encrypt_multi(passphrases: &[&[u8]], plaintext: &[u8]) -> Result<Vec<u8>> {
+    if passphrases.is_empty() {
+        bail!("at least one recipient passphrase is required");
+    }
+
+    let mut file_key = [0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut file_key);
+
+    let recipient_count = u32::try_from(passphrases.len()).context("too many recipients")?;
+    let mut output = Vec::new();
+    output.extend_from_slice(&recipient_count.to_be_bytes());
+    for passphrase in passphrases {
+        output.extend_from_slice(&wrap_file_key(passphrase, &file_key)?);
+    }
+
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let cipher = XSalsa20Poly1305::new(&file_key.into());
+    let nonce_obj = Nonce::from(nonce);
+    let sealed_box = cipher
+        .encrypt(&nonce_obj, plaintext)
+        .map_err(|e| anyhow::anyhow!("encryption failed: {}", e))?;
+    let sealed_box_len = sealed_box.len() as i64;
+
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&sealed_box_len.to_be_bytes());
+    output.extend_from_slice(&sealed_box);
+
+    Ok(output)
+}
+
+/// Seal `file_key` under a key derived from `passphrase` and a fresh random
+/// salt/nonce, producing a `RECIPIENT_WRAP_LEN`-byte `salt(8) + nonce(24) +
+/// wrapped_key(48)` blob.
+fn wrap_file_key(passphrase: &[u8], file_key: &[u8; KEY_LEN]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let wrap_key = derive_key(passphrase, &salt)?;
+    let cipher = XSalsa20Poly1305::new(&wrap_key.into());
+    let nonce_obj = Nonce::from(nonce);
+    let wrapped_key = cipher
+        .encrypt(&nonce_obj, file_key.as_slice())
+        .map_err(|e| anyhow::anyhow!("recipient key wrap failed: {}", e))?;
+
+    let mut out = Vec::with_capacity(RECIPIENT_WRAP_LEN);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&wrapped_key);
+    Ok(out)
+}
+
+/// Try to recover the file key from a single `RECIPIENT_WRAP_LEN`-byte wrap
+/// using `passphrase`, returning `None` if the passphrase is wrong for this
+/// particular wrap. Callers try every wrap in turn until one succeeds.
+fn try_unwrap_file_key(passphrase: &[u8], wrap: &[u8]) -> Option<[u8; KEY_LEN]> {
+    if wrap.len() != RECIPIENT_WRAP_LEN {
+        return None;
+    }
+    let salt: [u8; SALT_LEN] = wrap[..SALT_LEN].try_into().ok()?;
+    let nonce: [u8; NONCE_LEN] = wrap[SALT_LEN..SALT_LEN + NONCE_LEN].try_into().ok()?;
+    let wrapped_key = &wrap[SALT_LEN + NONCE_LEN..];
+
+    let wrap_key = derive_key(passphrase, &salt).ok()?;
+    let cipher = XSalsa20Poly1305::new(&wrap_key.into());
+    let nonce_obj = Nonce::from(nonce);
+    let file_key = cipher.decrypt(&nonce_obj, wrapped_key).ok()?;
+    file_key.try_into().ok()
+}
+
+/// A parsed multi-recipient ciphertext: the still-sealed recipient wraps
+/// plus the payload tail (sealed directly under the file key, not a
+/// passphrase-derived one).
+struct MultiRecipientCiphertext<'a> {
+    wraps: Vec<&'a [u8]>,
+    payload: &'a [u8],
+}
+
+fn parse_multi_recipient(ciphertext: &[u8]) -> Result<MultiRecipientCiphertext<'_>> {
+    if ciphertext.len() < size_of::<u32>() {
+        bail!("input likely truncated while reading recipient count");
+    }
+    let count_bytes: [u8; 4] = ciphertext[..size_of::<u32>()].try_into().unwrap();
+    let count = u32::from_be_bytes(count_bytes) as usize;
+    let mut pos = size_of::<u32>();
+
+    if count == 0 {
+        bail!("multi-recipient input has no recipients");
+    }
+
+    let mut wraps = Vec::with_capacity(count);
+    for _ in 0..count {
+        if ciphertext.len() < pos + RECIPIENT_WRAP_LEN {
+            bail!("input likely truncated while reading recipient wraps");
+        }
+        wraps.push(&ciphertext[pos..pos + RECIPIENT_WRAP_LEN]);
+        pos += RECIPIENT_WRAP_LEN;
+    }
+
+    Ok(MultiRecipientCiphertext {
+        wraps,
+        payload: &ciphertext[pos..],
+    })
+}
+
+/// A parsed `nonce(24)+length(8)+sealedbox` payload tail: like the
+/// salt-prefixed tail `parse_sealed_box` reads, but for a payload sealed
+/// directly under an already-recovered key rather than one derived from a
+/// passphrase-bound salt.
+struct KeyedSealedBox<'a> {
+    nonce: [u8; NONCE_LEN],
+    sealed_box: &'a [u8],
+}
+
+fn parse_keyed_sealed_box(payload: &[u8]) -> Result<KeyedSealedBox<'_>> {
+    let mut pos = 0;
+
+    if payload.len() < pos + NONCE_LEN {
+        bail!("input likely truncated while reading nonce");
+    }
+    let nonce: [u8; NONCE_LEN] = payload[pos..pos + NONCE_LEN].try_into().unwrap();
+    pos += NONCE_LEN;
+
+    if payload.len() < pos + size_of::<i64>() {
+        bail!("input likely truncated while reading sealed box");
+    }
+    let length_bytes: [u8; 8] = payload[pos..pos + size_of::<i64>()].try_into().unwrap();
+    let sealed_box_len = i64::from_be_bytes(length_bytes);
+    pos += size_of::<i64>();
+
+    if sealed_box_len < 0 {
+        bail!("negative sealed box length (when interpreted as a big-endian i64)");
+    }
+    if sealed_box_len > isize::MAX as i64 {
+        bail!("sealed box length exceeds this system's max isize");
+    }
+    let sealed_box_len = sealed_box_len as usize;
+
+    if sealed_box_len > payload.len() || payload.len() < pos + sealed_box_len {
+        bail!("truncated or corrupt input; claimed length greater than available input");
+    }
+    let sealed_box = &payload[pos..pos + sealed_box_len];
+    pos += sealed_box_len;
+
+    if pos < payload.len() {
+        bail!("invalid input: unexpected data after sealed box");
+    }
+
+    Ok(KeyedSealedBox { nonce, sealed_box })
+}
+
+/// Decrypt ciphertext produced by [`encrypt_multi`] using any one
+/// recipient's `passphrase`.
+pub fn decrypt_multi(passphrase: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let parsed = parse_multi_recipient(ciphertext)?;
+
+    let file_key = parsed
+        .wraps
+        .iter()
+        .find_map(|wrap| try_unwrap_file_key(passphrase, wrap))
+        .context("corrupt input, tampered-with data, or bad passphrase")?;
+
+    let payload = parse_keyed_sealed_box(parsed.payload)?;
+    let cipher = XSalsa20Poly1305::new(&file_key.into());
+    let nonce_obj = Nonce::from(payload.nonce);
+    let plaintext = cipher
+        .decrypt(&nonce_obj, payload.sealed_box)
+        .map_err(|_| anyhow::anyhow!("corrupt input, tampered-with data, or bad passphrase"))?;
+
+    Ok(plaintext)
+}
+
+/// Add a new recipient to ciphertext produced by [`encrypt_multi`] without
+/// re-encrypting the underlying plaintext. `existing_passphrase` must
+/// belong to one of the ciphertext's current recipients; `new_passphrase`
+/// becomes an additional recipient able to decrypt the same file key.
+pub fn add_recipient_multi(
+    ciphertext: &[u8],
+    existing_passphrase: &[u8],
+    new_passphrase: &[u8],
+) -> Result<Vec<u8>> {
+    let parsed = parse_multi_recipient(ciphertext)?;
+    let file_key = parsed
+        .wraps
+        .iter()
+        .find_map(|wrap| try_unwrap_file_key(existing_passphrase, wrap))
+        .context("corrupt input, tampered-with data, or bad passphrase")?;
+
+    let new_count = u32::try_from(parsed.wraps.len() + 1).context("too many recipients")?;
+    let mut output = Vec::new();
+    output.extend_from_slice(&new_count.to_be_bytes());
+    for wrap in &parsed.wraps {
+        output.extend_from_slice(wrap);
+    }
+    output.extend_from_slice(&wrap_file_key(new_passphrase, &file_key)?);
+    output.extend_from_slice(parsed.payload);
+
+    Ok(output)
+}
+
+/// Remove a recipient identified by `passphrase` from ciphertext produced
+/// by [`encrypt_multi`], without re-encrypting the underlying plaintext.
+/// Refuses to remove the last remaining recipient, since that would make
+/// the ciphertext permanently undecryptable.
+///
+/// `passphrase` must belong to the recipient being removed, since that's
+/// how their wrap is located among the others - there is no way for a
+/// remaining recipient to revoke someone else's access without knowing
+/// their passphrase. Revoking an uncooperative or compromised recipient
+/// (a lost device, a departing employee) instead requires re-encrypting
+/// the plaintext to a fresh file with [`encrypt_multi`].
+pub fn remove_recipient_multi(ciphertext: &[u8], passphrase: &[u8]) -> Result<Vec<u8>> {
+    let parsed = parse_multi_recipient(ciphertext)?;
+    if parsed.wraps.len() <= 1 {
+        bail!("cannot remove the last remaining recipient");
+    }
+
+    let removed_index = parsed
+        .wraps
+        .iter()
+        .position(|wrap| try_unwrap_file_key(passphrase, wrap).is_some())
+        .context("corrupt input, tampered-with data, or bad passphrase")?;
+
+    let new_count = u32::try_from(parsed.wraps.len() - 1).context("too many recipients")?;
+    let mut output = Vec::new();
+    output.extend_from_slice(&new_count.to_be_bytes());
+    for (index, wrap) in parsed.wraps.iter().enumerate() {
+        if index != removed_index {
+            output.extend_from_slice(wrap);
+        }
+    }
+    output.extend_from_slice(parsed.payload);
+
+    Ok(output)
+}
+
+/// Build the 24-byte nonce for chunk `counter` of a stream sealed under
+/// `prefix`, tagging the final chunk so truncation/extension of the stream
+/// is detected as an authentication failure rather than silently accepted.
+fn stream_nonce(prefix: &[u8; STREAM_NONCE_PREFIX_LEN], counter: u32, is_last: bool) -> Nonce {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..STREAM_NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[STREAM_NONCE_PREFIX_LEN..STREAM_NONCE_PREFIX_LEN + 4]
+        .copy_from_slice(&counter.to_be_bytes());
+    nonce[STREAM_NONCE_PREFIX_LEN + 4] = if is_last { 0x01 } else { 0x00 };
+    Nonce::from(nonce)
+}
+
+/// Read up to `buf.len()` bytes, looping until `buf` is full or EOF.
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        let n = reader
+            .read(&mut buf[total..])
+            .context("failed to read plaintext chunk")?;
+        if n == 0 {
+            break;
+        }
+        total += n;
+    }
+    Ok(total)
+}
+
+/// Encrypt the contents of `reader` with a passphrase, writing the chunked
+/// STREAM binary format to `writer`.
+///
+/// Unlike [`encrypt`], this never holds more than a couple of chunks of
+/// plaintext/ciphertext in memory at once, making it suitable for
+/// multi-gigabyte inputs. The on-disk layout is:
+///
+/// - salt: 8 bytes
+/// - nonce prefix: 19 bytes (random, constant for the whole stream)
+/// - one or more chunk records: `length(4, big-endian u32) || sealed_chunk`
+///
+/// Each chunk is sealed under a 24-byte nonce of `prefix || counter(4, BE) ||
+/// last_flag(1)`, where `last_flag` is `0x01` only for the final chunk. This
+/// binds each chunk to its position in the stream, so truncating the stream
+/// before the last-flagged chunk fails authentication on decrypt rather than
+/// silently returning a prefix of the plaintext.
+pub fn encrypt_stream<R: Read, W: Write>(
+    passphrase: &[u8],
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<()> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce_prefix = [0u8; STREAM_NONCE_PREFIX_LEN];
+    OsRng.fill_bytes(&mut nonce_prefix);
+
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = XSalsa20Poly1305::new(&key.into());
+
+    writer.write_all(&salt).context("failed to write salt")?;
+    writer
+        .write_all(&nonce_prefix)
+        .context("failed to write nonce prefix")?;
+
+    let mut buf = vec![0u8; STREAM_CHUNK_LEN];
+    let mut n = read_chunk(reader, &mut buf)?;
+    let mut counter: u32 = 0;
+
+    loop {
+        if n < STREAM_CHUNK_LEN {
+            // Short read (possibly zero bytes): this is the final chunk.
+            seal_and_write_chunk(&cipher, &nonce_prefix, counter, true, &buf[..n], writer)?;
+            break;
+        }
+
+        // Buffer was filled exactly; peek ahead to see whether more data
+        // follows before deciding if this chunk is the last one.
+        let mut next_buf = vec![0u8; STREAM_CHUNK_LEN];
+        let next_n = read_chunk(reader, &mut next_buf)?;
+        if next_n == 0 {
+            seal_and_write_chunk(&cipher, &nonce_prefix, counter, true, &buf[..n], writer)?;
+            break;
+        }
+
+        seal_and_write_chunk(&cipher, &nonce_prefix, counter, false, &buf[..n], writer)?;
+        counter = counter
+            .checked_add(1)
+            .context("stream has too many chunks")?;
+        buf = next_buf;
+        n = next_n;
+    }
+
+    Ok(())
+}
+
+fn seal_and_write_chunk<W: Write>(
+    cipher: &XSalsa20Poly1305,
+    nonce_prefix: &[u8; STREAM_NONCE_PREFIX_LEN],
+    counter: u32,
+    is_last: bool,
+    chunk: &[u8],
+    writer: &mut W,
+) -> Result<()> {
+    let nonce = stream_nonce(nonce_prefix, counter, is_last);
+    let sealed = cipher
+        .encrypt(&nonce, chunk)
+        .map_err(|e| anyhow::anyhow!("stream chunk encryption failed: {}", e))?;
+    let len = u32::try_from(sealed.len()).context("sealed chunk too large")?;
+    writer
+        .write_all(&len.to_be_bytes())
+        .context("failed to write chunk length")?;
+    writer
+        .write_all(&sealed)
+        .context("failed to write sealed chunk")?;
+    Ok(())
+}
+
+/// Read a big-endian u32 chunk length, returning `None` at a clean EOF
+/// (i.e. before any byte of the length could be read).
+fn read_chunk_len<R: Read>(reader: &mut R) -> Result<Option<u32>> {
+    let mut len_bytes = [0u8; 4];
+    let mut total = 0;
+    while total < len_bytes.len() {
+        let n = reader
+            .read(&mut len_bytes[total..])
+            .context("failed to read chunk length")?;
+        if n == 0 {
+            if total == 0 {
+                return Ok(None);
+            }
+            bail!("input likely truncated while reading chunk length");
+        }
+        total += n;
+    }
+    Ok(Some(u32::from_be_bytes(len_bytes)))
+}
+
+/// Decrypt a chunked STREAM binary blob produced by [`encrypt_stream`],
+/// writing plaintext to `writer` as each chunk authenticates.
+///
+/// Returns an error if the passphrase is wrong, any chunk fails to
+/// authenticate, or the stream ends without ever producing a chunk tagged
+/// as the final one. Because each chunk's nonce is bound to both its
+/// position (the counter) and whether it was the real final chunk, dropping
+/// the final chunk or reordering chunks both fail authentication rather than
+/// silently returning a truncated or rearranged plaintext.
+pub fn decrypt_stream<R: Read, W: Write>(
+    passphrase: &[u8],
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<()> {
+    let mut salt = [0u8; SALT_LEN];
+    reader
+        .read_exact(&mut salt)
+        .context("input likely truncated while reading salt")?;
+    let mut nonce_prefix = [0u8; STREAM_NONCE_PREFIX_LEN];
+    reader
+        .read_exact(&mut nonce_prefix)
+        .context("input likely truncated while reading nonce prefix")?;
+
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = XSalsa20Poly1305::new(&key.into());
+
+    let mut counter: u32 = 0;
+    let mut pending_len =
+        read_chunk_len(reader)?.context("input likely truncated: stream has no chunks")?;
+
+    loop {
+        let sealed_len = pending_len as usize;
+        if sealed_len > STREAM_MAX_SEALED_CHUNK_LEN {
+            bail!("chunk length exceeds maximum allowed size");
+        }
+        let mut sealed = vec![0u8; sealed_len];
+        reader
+            .read_exact(&mut sealed)
+            .context("input likely truncated while reading sealed chunk")?;
+
+        let next_len = read_chunk_len(reader)?;
+        let is_last = next_len.is_none();
+
+        let nonce = stream_nonce(&nonce_prefix, counter, is_last);
+        let plaintext = cipher.decrypt(&nonce, sealed.as_slice()).map_err(|_| {
+            anyhow::anyhow!("corrupt input, tampered-with data, truncated stream, or bad passphrase")
+        })?;
+        writer
+            .write_all(&plaintext)
+            .context("failed to write decrypted chunk")?;
+
+        if is_last {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .context("stream has too many chunks")?;
+        pending_len = next_len.unwrap();
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,12 +1327,12 @@ mod tests {
         let result = decrypt(b"wrong", &ciphertext);
 
         assert!(result.is_err());
+        let err = result.unwrap_err();
         assert!(
-            result
-                .unwrap_err()
-                .to_string()
+            err.to_string()
                 .contains("corrupt input, tampered-with data, or bad passphrase")
         );
+        assert_eq!(err.kind, Some(ErrorKind::AuthenticationFailed));
     }
 
     #[test]
@@ -249,12 +1341,12 @@ mod tests {
         let result = decrypt(b"test", &ciphertext);
 
         assert!(result.is_err());
+        let err = result.unwrap_err();
         assert!(
-            result
-                .unwrap_err()
-                .to_string()
+            err.to_string()
                 .contains("input likely truncated while reading salt")
         );
+        assert_eq!(err.kind, Some(ErrorKind::TruncatedInput));
     }
 
     #[test]
@@ -263,12 +1355,12 @@ mod tests {
         let result = decrypt(b"test", &ciphertext);
 
         assert!(result.is_err());
+        let err = result.unwrap_err();
         assert!(
-            result
-                .unwrap_err()
-                .to_string()
+            err.to_string()
                 .contains("input likely truncated while reading nonce")
         );
+        assert_eq!(err.kind, Some(ErrorKind::TruncatedInput));
     }
 
     #[test]
@@ -277,12 +1369,12 @@ mod tests {
         let result = decrypt(b"test", &ciphertext);
 
         assert!(result.is_err());
+        let err = result.unwrap_err();
         assert!(
-            result
-                .unwrap_err()
-                .to_string()
+            err.to_string()
                 .contains("input likely truncated while reading sealed box")
         );
+        assert_eq!(err.kind, Some(ErrorKind::TruncatedInput));
     }
 
     #[test]
@@ -296,12 +1388,9 @@ mod tests {
         let result = decrypt(b"test", &ciphertext);
 
         assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("negative sealed box length")
-        );
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("negative sealed box length"));
+        assert_eq!(err.kind, Some(ErrorKind::BinaryFormat));
     }
 
     #[test]
@@ -319,11 +1408,13 @@ mod tests {
         let result = decrypt(passphrase, &ciphertext);
 
         assert!(result.is_err());
+        let err = result.unwrap_err();
         assert!(
-            result.unwrap_err().to_string().contains(
+            err.to_string().contains(
                 "truncated or corrupt input; claimed length greater than available input"
             )
         );
+        assert_eq!(err.kind, Some(ErrorKind::TruncatedInput));
     }
 
     #[test]
@@ -338,12 +1429,12 @@ mod tests {
         let result = decrypt(passphrase, &ciphertext);
 
         assert!(result.is_err());
+        let err = result.unwrap_err();
         assert!(
-            result
-                .unwrap_err()
-                .to_string()
+            err.to_string()
                 .contains("invalid input: unexpected data after sealed box")
         );
+        assert_eq!(err.kind, Some(ErrorKind::TrailingData));
     }
 
     #[test]
@@ -390,6 +1481,477 @@ mod tests {
         assert_eq!(plaintext, decrypted);
     }
 
+    #[test]
+    fn test_stream_roundtrip_empty() {
+        let passphrase = b"test";
+        let plaintext = b"";
+        let mut ciphertext = Vec::new();
+        encrypt_stream(passphrase, &mut &plaintext[..], &mut ciphertext).unwrap();
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(passphrase, &mut &ciphertext[..], &mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_stream_roundtrip_multi_chunk() {
+        let passphrase = b"test";
+        // More than two chunk's worth so both the lookahead and final-chunk
+        // paths are exercised.
+        let plaintext = vec![0x5Au8; STREAM_CHUNK_LEN * 2 + 17];
+        let mut ciphertext = Vec::new();
+        encrypt_stream(passphrase, &mut &plaintext[..], &mut ciphertext).unwrap();
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(passphrase, &mut &ciphertext[..], &mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_stream_roundtrip_exact_chunk_boundary() {
+        let passphrase = b"test";
+        let plaintext = vec![0x11u8; STREAM_CHUNK_LEN];
+        let mut ciphertext = Vec::new();
+        encrypt_stream(passphrase, &mut &plaintext[..], &mut ciphertext).unwrap();
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(passphrase, &mut &ciphertext[..], &mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_stream_wrong_passphrase() {
+        let plaintext = vec![0x42u8; 100];
+        let mut ciphertext = Vec::new();
+        encrypt_stream(b"correct", &mut &plaintext[..], &mut ciphertext).unwrap();
+
+        let mut decrypted = Vec::new();
+        let result = decrypt_stream(b"wrong", &mut &ciphertext[..], &mut decrypted);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_stream_truncation_detected() {
+        let passphrase = b"test";
+        let plaintext = vec![0x42u8; STREAM_CHUNK_LEN * 2];
+        let mut ciphertext = Vec::new();
+        encrypt_stream(passphrase, &mut &plaintext[..], &mut ciphertext).unwrap();
+
+        // Cut the last 80 bytes so the final chunk's record is truncated.
+        let truncated = &ciphertext[..ciphertext.len() - 80];
+        let mut decrypted = Vec::new();
+        let result = decrypt_stream(passphrase, &mut &truncated[..], &mut decrypted);
+        assert!(
+            result.is_err(),
+            "truncated stream must not decrypt successfully"
+        );
+    }
+
+    /// Splits a stream ciphertext into its header (salt + nonce prefix) and
+    /// the list of `(length, sealed_chunk)` records that follow it.
+    fn split_stream_records(ciphertext: &[u8]) -> (&[u8], Vec<(u32, &[u8])>) {
+        let header_len = SALT_LEN + STREAM_NONCE_PREFIX_LEN;
+        let (header, mut rest) = ciphertext.split_at(header_len);
+        let mut records = Vec::new();
+        while !rest.is_empty() {
+            let (len_bytes, tail) = rest.split_at(4);
+            let len = u32::from_be_bytes(len_bytes.try_into().unwrap());
+            let (sealed, tail) = tail.split_at(len as usize);
+            records.push((len, sealed));
+            rest = tail;
+        }
+        (header, records)
+    }
+
+    fn join_stream_records(header: &[u8], records: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut out = header.to_vec();
+        for (len, sealed) in records {
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(sealed);
+        }
+        out
+    }
+
+    #[test]
+    fn test_stream_dropping_final_chunk_fails_closed() {
+        let passphrase = b"test";
+        let plaintext = vec![0x42u8; STREAM_CHUNK_LEN * 2 + 17];
+        let mut ciphertext = Vec::new();
+        encrypt_stream(passphrase, &mut &plaintext[..], &mut ciphertext).unwrap();
+
+        let (header, records) = split_stream_records(&ciphertext);
+        assert_eq!(records.len(), 3, "expected three chunks for this input size");
+
+        // Drop exactly the real final (last-flagged) chunk, leaving an
+        // otherwise well-formed stream that ends after a non-final chunk.
+        let truncated = join_stream_records(header, &records[..records.len() - 1]);
+
+        let mut decrypted = Vec::new();
+        let result = decrypt_stream(passphrase, &mut &truncated[..], &mut decrypted);
+        assert!(
+            result.is_err(),
+            "dropping the final chunk must fail authentication, not silently truncate plaintext"
+        );
+    }
+
+    #[test]
+    fn test_stream_reordered_chunks_fail_closed() {
+        let passphrase = b"test";
+        let plaintext = vec![0x7Eu8; STREAM_CHUNK_LEN * 2 + 5];
+        let mut ciphertext = Vec::new();
+        encrypt_stream(passphrase, &mut &plaintext[..], &mut ciphertext).unwrap();
+
+        let (header, mut records) = split_stream_records(&ciphertext);
+        assert_eq!(records.len(), 3, "expected three chunks for this input size");
+        records.swap(0, 1);
+        let reordered = join_stream_records(header, &records);
+
+        let mut decrypted = Vec::new();
+        let result = decrypt_stream(passphrase, &mut &reordered[..], &mut decrypted);
+        assert!(
+            result.is_err(),
+            "reordered chunks must fail authentication since each is bound to its position"
+        );
+    }
+
+    #[test]
+    fn test_stream_trailing_data_after_final_chunk_fails_closed() {
+        let passphrase = b"test";
+        let plaintext = vec![0x99u8; STREAM_CHUNK_LEN + 3];
+        let mut ciphertext = Vec::new();
+        encrypt_stream(passphrase, &mut &plaintext[..], &mut ciphertext).unwrap();
+
+        // Append extra bytes after the flagged final chunk. The chunk that
+        // was actually sealed as last no longer appears last from the
+        // decryptor's point of view, so it must be rejected rather than
+        // decrypted with the trailing bytes silently ignored.
+        ciphertext.extend_from_slice(&[0xFFu8; 16]);
+
+        let mut decrypted = Vec::new();
+        let result = decrypt_stream(passphrase, &mut &ciphertext[..], &mut decrypted);
+        assert!(
+            result.is_err(),
+            "trailing data after the final chunk must not decrypt successfully"
+        );
+    }
+
+    #[test]
+    fn test_compression_roundtrip_none() {
+        let passphrase = b"test";
+        let plaintext = b"hello world";
+        let ciphertext =
+            encrypt_with_compression(passphrase, plaintext, Compression::None).unwrap();
+        let decrypted = decrypt_with_compression(passphrase, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_compression_roundtrip_zstd() {
+        let passphrase = b"test";
+        let plaintext = vec![b'a'; 10_000]; // highly compressible
+        let compressed_ciphertext =
+            encrypt_with_compression(passphrase, &plaintext, Compression::Zstd).unwrap();
+        let uncompressed_ciphertext =
+            encrypt_with_compression(passphrase, &plaintext, Compression::None).unwrap();
+
+        assert!(compressed_ciphertext.len() < uncompressed_ciphertext.len());
+
+        let decrypted = decrypt_with_compression(passphrase, &compressed_ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_compression_unknown_algorithm() {
+        let passphrase = b"test";
+        let mut ciphertext = encrypt_with_compression(passphrase, b"hi", Compression::None).unwrap();
+        ciphertext[0] = 0xFF;
+        let result = decrypt_with_compression(passphrase, &ciphertext);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_multi_roundtrip_single_recipient() {
+        let passphrase: &[u8] = b"test";
+        let plaintext = b"hello world";
+        let ciphertext = encrypt_multi(&[passphrase], plaintext).unwrap();
+        let decrypted = decrypt_multi(passphrase, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_multi_roundtrip_each_recipient_can_decrypt() {
+        let passphrases: Vec<&[u8]> = vec![b"alice", b"bob", b"carol"];
+        let plaintext = b"shared secret";
+        let ciphertext = encrypt_multi(&passphrases, plaintext).unwrap();
+        for passphrase in &passphrases {
+            let decrypted = decrypt_multi(passphrase, &ciphertext).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_multi_wrong_passphrase() {
+        let ciphertext = encrypt_multi(&[b"alice", b"bob"], b"hello world").unwrap();
+        let result = decrypt_multi(b"mallory", &ciphertext);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_multi_requires_at_least_one_recipient() {
+        let result = encrypt_multi(&[], b"hello world");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_add_recipient_multi_roundtrip() {
+        let ciphertext = encrypt_multi(&[b"alice"], b"hello world").unwrap();
+        let updated = add_recipient_multi(&ciphertext, b"alice", b"bob").unwrap();
+
+        assert_eq!(decrypt_multi(b"alice", &updated).unwrap(), b"hello world");
+        assert_eq!(decrypt_multi(b"bob", &updated).unwrap(), b"hello world");
+    }
+
+    #[test]
+    fn test_add_recipient_multi_wrong_existing_passphrase() {
+        let ciphertext = encrypt_multi(&[b"alice"], b"hello world").unwrap();
+        let result = add_recipient_multi(&ciphertext, b"mallory", b"bob");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_remove_recipient_multi_roundtrip() {
+        let ciphertext = encrypt_multi(&[b"alice", b"bob"], b"hello world").unwrap();
+        let updated = remove_recipient_multi(&ciphertext, b"bob").unwrap();
+
+        assert_eq!(decrypt_multi(b"alice", &updated).unwrap(), b"hello world");
+        assert!(decrypt_multi(b"bob", &updated).is_err());
+    }
+
+    #[test]
+    fn test_remove_recipient_multi_refuses_to_remove_last_recipient() {
+        let ciphertext = encrypt_multi(&[b"alice"], b"hello world").unwrap();
+        let result = remove_recipient_multi(&ciphertext, b"alice");
+        assert!(result.is_err());
+        // The ciphertext is returned unmodified on failure: alice can still decrypt.
+        assert_eq!(decrypt_multi(b"alice", &ciphertext).unwrap(), b"hello world");
+    }
+
+    #[test]
+    fn test_remove_recipient_multi_wrong_passphrase() {
+        let ciphertext = encrypt_multi(&[b"alice", b"bob"], b"hello world").unwrap();
+        let result = remove_recipient_multi(&ciphertext, b"mallory");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_multi_truncated_recipient_count() {
+        let ciphertext = vec![0u8, 0u8];
+        let result = decrypt_multi(b"alice", &ciphertext);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_params_roundtrip() {
+        let passphrase = b"test";
+        let plaintext = b"hello world";
+        // Deliberately cheap params so the test runs fast.
+        let params = ScryptParams {
+            log2_n: 4,
+            r: 1,
+            p: 1,
+        };
+        let ciphertext = encrypt_with_params(passphrase, plaintext, params, false).unwrap();
+        assert_eq!(&ciphertext[..3], &[4, 1, 1]);
+
+        let decrypted = decrypt_with_params(passphrase, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_encrypt_with_options_roundtrip() {
+        let passphrase = b"test";
+        let plaintext = b"sensitive options test";
+        // Deliberately cheap params so the test runs fast.
+        let options = EncryptOptions::with_params(ScryptParams {
+            log2_n: 4,
+            r: 1,
+            p: 1,
+        });
+        let ciphertext = encrypt_with_options(passphrase, plaintext, options).unwrap();
+        let decrypted = decrypt_with_params(passphrase, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_encrypt_options_presets_validate() {
+        EncryptOptions::INTERACTIVE.params.validate().unwrap();
+        EncryptOptions::SENSITIVE.params.validate().unwrap();
+    }
+
+    #[test]
+    fn test_kdf_scrypt_roundtrip() {
+        let passphrase = b"test";
+        let plaintext = b"hello via the kdf-agility layer";
+        // Deliberately cheap params so the test runs fast.
+        let kdf = Kdf::Scrypt {
+            log_n: 4,
+            r: 1,
+            p: 1,
+        };
+        let ciphertext = encrypt_with_kdf(passphrase, plaintext, kdf).unwrap();
+        assert_eq!(ciphertext[0], KDF_DISCRIMINANT_SCRYPT);
+
+        let decrypted = decrypt_with_kdf(passphrase, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_kdf_argon2id_roundtrip() {
+        let passphrase = b"test";
+        let plaintext = b"hello via argon2id";
+        // Deliberately cheap params so the test runs fast.
+        let kdf = Kdf::Argon2id {
+            m_cost: 8,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        let ciphertext = encrypt_with_kdf(passphrase, plaintext, kdf).unwrap();
+        assert_eq!(ciphertext[0], KDF_DISCRIMINANT_ARGON2ID);
+
+        let decrypted = decrypt_with_kdf(passphrase, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_kdf_wrong_passphrase() {
+        let kdf = Kdf::Argon2id {
+            m_cost: 8,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        let ciphertext = encrypt_with_kdf(b"correct", b"secret", kdf).unwrap();
+        let result = decrypt_with_kdf(b"wrong", &ciphertext);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_kdf_rejects_excessive_argon2id_memory() {
+        let kdf = Kdf::Argon2id {
+            m_cost: 16 * 1024 * 1024, // 16 GiB, absurd
+            t_cost: 1,
+            p_cost: 1,
+        };
+        assert!(encrypt_with_kdf(b"test", b"data", kdf).is_err());
+    }
+
+    #[test]
+    fn test_kdf_default_is_scrypt() {
+        assert_eq!(Kdf::default(), Kdf::SCRYPT_DEFAULT);
+    }
+
+    #[test]
+    fn test_kdf_deterministic_matches_given_salt_and_nonce() {
+        let passphrase = b"test";
+        let plaintext = b"deterministic golden vector input";
+        let salt = [1u8; SALT_LEN];
+        let nonce = [2u8; NONCE_LEN];
+        let kdf = Kdf::Argon2id {
+            m_cost: 8,
+            t_cost: 1,
+            p_cost: 1,
+        };
+
+        let a = encrypt_deterministic_with_kdf(passphrase, plaintext, kdf, &salt, &nonce).unwrap();
+        let b = encrypt_deterministic_with_kdf(passphrase, plaintext, kdf, &salt, &nonce).unwrap();
+        assert_eq!(a, b, "same inputs must produce identical ciphertext");
+
+        let decrypted = decrypt_with_kdf(passphrase, &a).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_params_wrong_passphrase() {
+        let params = ScryptParams {
+            log2_n: 4,
+            r: 1,
+            p: 1,
+        };
+        let ciphertext = encrypt_with_params(b"correct", b"secret", params, false).unwrap();
+        let result = decrypt_with_params(b"wrong", &ciphertext);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_params_rejects_absurd_cost() {
+        let params = ScryptParams {
+            log2_n: 30,
+            r: 255,
+            p: 255,
+        };
+        assert!(params.validate().is_err());
+        assert!(encrypt_with_params(b"test", b"data", params, false).is_err());
+    }
+
+    #[test]
+    fn test_params_allow_expensive_bypasses_memory_ceiling() {
+        let params = ScryptParams {
+            log2_n: 22,
+            r: 8,
+            p: 1,
+        };
+        assert!(params.validate().is_err());
+        assert!(params.validate_allow_expensive().is_ok());
+        assert!(encrypt_with_params(b"test", b"data", params, false).is_err());
+        assert!(encrypt_with_params(b"test", b"data", params, true).is_ok());
+    }
+
+    #[test]
+    fn test_params_allow_expensive_still_rejects_zero_values() {
+        let params = ScryptParams {
+            log2_n: 0,
+            r: 1,
+            p: 1,
+        };
+        assert!(params.validate_allow_expensive().is_err());
+    }
+
+    #[test]
+    fn test_params_rejects_zero_values() {
+        assert!(
+            ScryptParams {
+                log2_n: 0,
+                r: 1,
+                p: 1
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            ScryptParams {
+                log2_n: 4,
+                r: 0,
+                p: 1
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            ScryptParams {
+                log2_n: 4,
+                r: 1,
+                p: 0
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_decrypt_with_params_truncated_header() {
+        let result = decrypt_with_params(b"test", &[1, 2]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_cross_implementation_compatibility() {
         // This test uses the exact same parameters as the Go implementation's
@@ -7,24 +7,255 @@
 //! - Safe to pass unescaped in a POSIX shell
 
 use crate::error::{ErrorCategory, ErrorKind, Result, SaltyboxError};
-use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
 
 /// Magic prefix for all saltybox versions
 const MAGIC_PREFIX: &str = "saltybox";
 
-/// Version 1 magic marker
+/// Version 1 magic marker: the original single-box format
 const V1_MAGIC: &str = "saltybox1:";
 
+/// PGP-style armor magic marker: same underlying bytes as [`Version::V1`],
+/// but line-wrapped standard base64 with a CRC-24 checksum footer (see
+/// [`wrap_armor2`]/[`unwrap_version`]) instead of one unbroken url-safe token.
+pub const ARMOR2_MAGIC: &str = "saltybox2:";
+
+/// Width, in characters, of each base64 line in the `saltybox2:` armor.
+const ARMOR2_LINE_WIDTH: usize = 64;
+
+/// CRC-24 parameters as used by RFC 4880 (OpenPGP) ASCII armor.
+const CRC24_INIT: u32 = 0x00B7_04CE;
+const CRC24_POLY: u32 = 0x0186_4CFB;
+const CRC24_MASK: u32 = 0x00FF_FFFF;
+
+/// Computes the RFC 4880 CRC-24 checksum over `data`.
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc = CRC24_INIT;
+    for &byte in data {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & CRC24_MASK
+}
+
+/// Streaming chunked-AEAD magic marker (see `secretcrypt::encrypt_stream`)
+pub const STREAM1_MAGIC: &str = "saltybox-stream1:";
+
+/// Self-describing compressed-plaintext magic marker (see
+/// `secretcrypt::encrypt_with_compression`)
+pub const COMPRESSED1_MAGIC: &str = "saltybox-c1:";
+
+/// Public-key (recipient) sealed-box magic marker (see
+/// `pkcrypt::encrypt_to_recipient`)
+pub const PK1_MAGIC: &str = "saltybox-pk1:";
+
+/// Self-describing tunable-scrypt-cost magic marker (see
+/// `secretcrypt::encrypt_with_params`)
+pub const PARAMS1_MAGIC: &str = "saltybox-params1:";
+
+/// Self-describing pluggable-KDF magic marker (see
+/// `secretcrypt::encrypt_with_kdf`)
+pub const KDF1_MAGIC: &str = "saltybox-kdf1:";
+
+/// Multi-recipient passphrase-wrapped magic marker (see
+/// `secretcrypt::encrypt_multi`)
+pub const MULTI1_MAGIC: &str = "saltybox-multi1:";
+
+/// Which on-disk container a piece of armored text uses.
+///
+/// `unwrap` only ever returns [`Version::V1`] (for backward compatibility);
+/// callers that need to dispatch to a different decryption routine should use
+/// [`unwrap_version`] instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Version {
+    /// The original `salt|nonce|length|sealedbox` single-box format. Also
+    /// produced by decoding the `saltybox2:` PGP-style armor (see
+    /// [`wrap_armor2`]); the two are the same bytes in different text
+    /// encodings, so both are reported as `V1`.
+    V1,
+    /// The chunked STREAM construction produced by `secretcrypt::encrypt_stream`.
+    Stream1,
+    /// The self-describing compression header produced by
+    /// `secretcrypt::encrypt_with_compression`.
+    Compressed1,
+    /// The public-key sealed-box format produced by
+    /// `pkcrypt::encrypt_to_recipient`.
+    Pk1,
+    /// The self-describing tunable-scrypt-cost format produced by
+    /// `secretcrypt::encrypt_with_params`.
+    Params1,
+    /// The self-describing pluggable-KDF format produced by
+    /// `secretcrypt::encrypt_with_kdf`.
+    Kdf1,
+    /// The multi-recipient passphrase-wrapped format produced by
+    /// `secretcrypt::encrypt_multi`.
+    MultiRecipient1,
+}
+
+impl Version {
+    fn magic(self) -> &'static str {
+        match self {
+            Version::V1 => V1_MAGIC,
+            Version::Stream1 => STREAM1_MAGIC,
+            Version::Compressed1 => COMPRESSED1_MAGIC,
+            Version::Pk1 => PK1_MAGIC,
+            Version::Params1 => PARAMS1_MAGIC,
+            Version::Kdf1 => KDF1_MAGIC,
+            Version::MultiRecipient1 => MULTI1_MAGIC,
+        }
+    }
+}
+
 /// Wrap bytes in armor, returning the armored string
 ///
 /// Format: saltybox1:{base64url-no-padding}
 pub fn wrap(body: &[u8]) -> String {
+    wrap_version(body, Version::V1)
+}
+
+/// Wrap bytes in armor using a specific container version's magic marker.
+pub fn wrap_version(body: &[u8], version: Version) -> String {
     let encoded = URL_SAFE_NO_PAD.encode(body);
-    format!("{}{}", V1_MAGIC, encoded)
+    format!("{}{}", version.magic(), encoded)
 }
 
 /// Unwrap an armored string, returning the original bytes
+///
+/// This only accepts the original [`Version::V1`] format; use
+/// [`unwrap_version`] to also accept newer container versions.
 pub fn unwrap(armored: &str) -> Result<Vec<u8>> {
+    let (version, body) = unwrap_version(armored)?;
+    if version != Version::V1 {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::ArmoringFromFuture,
+            "input is not in the saltybox1 format",
+        ));
+    }
+    Ok(body)
+}
+
+/// Wrap bytes in the `saltybox2:` PGP-style armor: standard (non-URL-safe)
+/// base64 broken into fixed-width lines, followed by a `=`-prefixed base64
+/// CRC-24 checksum line over the raw bytes, modeled on RFC 4880 ASCII armor.
+/// This carries the same bytes as [`wrap`] (the [`Version::V1`] format), just
+/// encoded so it survives being pasted into email or wikis that hard-wrap
+/// lines, with transcription errors caught before decryption is attempted.
+pub fn wrap_armor2(body: &[u8]) -> String {
+    let encoded = STANDARD.encode(body);
+    let mut out = String::with_capacity(ARMOR2_MAGIC.len() + encoded.len() + encoded.len() / ARMOR2_LINE_WIDTH + 16);
+    out.push_str(ARMOR2_MAGIC);
+    out.push('\n');
+    for line in encoded.as_bytes().chunks(ARMOR2_LINE_WIDTH) {
+        out.push_str(std::str::from_utf8(line).expect("base64 output is ASCII"));
+        out.push('\n');
+    }
+
+    let crc = crc24(body).to_be_bytes();
+    out.push('=');
+    out.push_str(&STANDARD.encode([crc[1], crc[2], crc[3]]));
+    out.push('\n');
+    out
+}
+
+/// Strips a single line's leading/trailing whitespace and, if present,
+/// leading `>` email/chat quote markers (and any whitespace between them).
+fn strip_quote_prefix(line: &str) -> &str {
+    let mut s = line.trim();
+    while let Some(rest) = s.strip_prefix('>') {
+        s = rest.trim_start();
+    }
+    s
+}
+
+/// Parses the body of a `saltybox2:` armor (the part after the magic
+/// marker) into its decoded bytes, checking the CRC-24 footer.
+/// When `tolerant` is set, each line has leading `>` quote markers
+/// stripped first, so blobs quoted in an email thread or chat log still
+/// parse; strict `unwrap`/`unwrap_version` leave quote markers alone.
+fn parse_armor2_body(rest: &str, tolerant: bool) -> Result<Vec<u8>> {
+    let mut data = String::with_capacity(rest.len());
+    let mut checksum_line = None;
+    for line in rest.lines() {
+        let line = if tolerant {
+            strip_quote_prefix(line)
+        } else {
+            line.trim()
+        };
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(sum) = line.strip_prefix('=') {
+            checksum_line = Some(sum.to_string());
+            continue;
+        }
+        data.push_str(line);
+    }
+
+    let checksum_line = checksum_line.ok_or_else(|| {
+        SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::ArmoringInvalid,
+            "saltybox2 input is missing its checksum line",
+        )
+    })?;
+
+    let body = STANDARD.decode(&data).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::User,
+            ErrorKind::ArmoringDecode,
+            format!("base64 decoding failed: {}", e),
+            e,
+        )
+    })?;
+
+    let checksum = STANDARD.decode(checksum_line).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::User,
+            ErrorKind::ArmoringDecode,
+            format!("checksum base64 decoding failed: {}", e),
+            e,
+        )
+    })?;
+    let &[c0, c1, c2] = checksum.as_slice() else {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::ArmoringInvalid,
+            "saltybox2 checksum is not 3 bytes",
+        ));
+    };
+    let claimed_crc = ((c0 as u32) << 16) | ((c1 as u32) << 8) | c2 as u32;
+    if claimed_crc != crc24(&body) {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::ArmoringChecksumMismatch,
+            "saltybox2 checksum does not match decoded data; input may be truncated or corrupted",
+        ));
+    }
+
+    Ok(body)
+}
+
+/// Unwrap a `saltybox2:` PGP-style armored string (see [`wrap_armor2`]).
+fn unwrap_armor2(armored: &str) -> Result<Vec<u8>> {
+    let rest = armored
+        .strip_prefix(ARMOR2_MAGIC)
+        .expect("caller already matched the saltybox2 prefix");
+    parse_armor2_body(rest, false)
+}
+
+/// Unwrap an armored string, returning both the detected container version
+/// and the original bytes.
+pub fn unwrap_version(armored: &str) -> Result<(Version, Vec<u8>)> {
     if armored.len() < V1_MAGIC.len() {
         return Err(SaltyboxError::with_kind(
             ErrorCategory::User,
@@ -33,17 +264,35 @@ pub fn unwrap(armored: &str) -> Result<Vec<u8>> {
         ));
     }
 
-    if let Some(encoded) = armored.strip_prefix(V1_MAGIC) {
-        let body = URL_SAFE_NO_PAD.decode(encoded).map_err(|e| {
-            SaltyboxError::with_kind_and_source(
-                ErrorCategory::User,
-                ErrorKind::ArmoringDecode,
-                format!("base64 decoding failed: {}", e),
-                e,
-            )
-        })?;
-        Ok(body)
-    } else if armored.starts_with(MAGIC_PREFIX) {
+    if armored.starts_with(ARMOR2_MAGIC) {
+        let body = unwrap_armor2(armored)?;
+        return Ok((Version::V1, body));
+    }
+
+    let known_versions = [
+        Version::V1,
+        Version::Stream1,
+        Version::Compressed1,
+        Version::Pk1,
+        Version::Params1,
+        Version::Kdf1,
+        Version::MultiRecipient1,
+    ];
+    for version in known_versions {
+        if let Some(encoded) = armored.strip_prefix(version.magic()) {
+            let body = URL_SAFE_NO_PAD.decode(encoded).map_err(|e| {
+                SaltyboxError::with_kind_and_source(
+                    ErrorCategory::User,
+                    ErrorKind::ArmoringDecode,
+                    format!("base64 decoding failed: {}", e),
+                    e,
+                )
+            })?;
+            return Ok((version, body));
+        }
+    }
+
+    if armored.starts_with(MAGIC_PREFIX) {
         Err(SaltyboxError::with_kind(
             ErrorCategory::User,
             ErrorKind::ArmoringFromFuture,
@@ -58,6 +307,122 @@ pub fn unwrap(armored: &str) -> Result<Vec<u8>> {
     }
 }
 
+/// Returns whether `ch` can appear in the url-safe-no-padding base64
+/// alphabet used by every container version except `saltybox2:`.
+fn is_base64url_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'
+}
+
+/// Every magic marker this crate recognizes, checked by [`find_magic_token`]
+/// against each occurrence of [`MAGIC_PREFIX`] it finds in a tolerant input.
+/// Kept as one list so a new version's marker (plain `saltybox<N>:` or
+/// hyphenated like `saltybox-kdf1:`) only has to be added here once.
+const KNOWN_MAGICS: &[&str] = &[
+    ARMOR2_MAGIC,
+    V1_MAGIC,
+    STREAM1_MAGIC,
+    COMPRESSED1_MAGIC,
+    PK1_MAGIC,
+    PARAMS1_MAGIC,
+    KDF1_MAGIC,
+    MULTI1_MAGIC,
+];
+
+/// Finds the first occurrence of a marker from [`KNOWN_MAGICS`] in `text`
+/// and returns the byte offset of its start and the magic string itself.
+fn find_magic_token(text: &str) -> Option<(usize, &str)> {
+    let mut search_from = 0;
+    while let Some(rel) = text[search_from..].find(MAGIC_PREFIX) {
+        let start = search_from + rel;
+        if let Some(magic) = KNOWN_MAGICS.iter().find(|magic| text[start..].starts_with(**magic)) {
+            return Some((start, &text[start..start + magic.len()]));
+        }
+        search_from = start + 1;
+        if search_from >= text.len() {
+            break;
+        }
+    }
+    None
+}
+
+/// Tolerant variant of [`unwrap_version`] for payloads that were copied out
+/// of a chat message, email quote, or log line rather than passed machine to
+/// machine. Scans `text` for the first `saltybox<N>:` token, then consumes
+/// the run of encoded characters that follows it -- skipping over
+/// interspersed whitespace, newlines, and `>` quote markers -- stopping at
+/// the first character that cannot belong to the format's encoding. As with
+/// `unwrap_version`, a recognized-but-unsupported version still yields
+/// [`ErrorKind::ArmoringFromFuture`].
+pub fn unwrap_tolerant(text: &str) -> Result<(Version, Vec<u8>)> {
+    let Some((magic_start, magic)) = find_magic_token(text) else {
+        // A `saltybox<tag>:` token whose tag isn't in KNOWN_MAGICS is a
+        // version from the future, not missing armor.
+        let claims_saltybox = text.match_indices(MAGIC_PREFIX).any(|(start, _)| {
+            let rest = &text[start + MAGIC_PREFIX.len()..];
+            let tag_len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+                .unwrap_or(rest.len());
+            tag_len > 0 && rest[tag_len..].starts_with(':')
+        });
+        if claims_saltybox {
+            return Err(SaltyboxError::with_kind(
+                ErrorCategory::User,
+                ErrorKind::ArmoringFromFuture,
+                "input claims to be saltybox, but not a version we support",
+            ));
+        }
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::ArmoringInvalid,
+            "no saltybox marker found in input",
+        ));
+    };
+    let body_start = magic_start + magic.len();
+
+    if magic == ARMOR2_MAGIC {
+        let body = parse_armor2_body(&text[body_start..], true)?;
+        return Ok((Version::V1, body));
+    }
+
+    let known_versions = [
+        Version::V1,
+        Version::Stream1,
+        Version::Compressed1,
+        Version::Pk1,
+        Version::Params1,
+        Version::Kdf1,
+        Version::MultiRecipient1,
+    ];
+    let Some(version) = known_versions.into_iter().find(|v| v.magic() == magic) else {
+        return Err(SaltyboxError::with_kind(
+            ErrorCategory::User,
+            ErrorKind::ArmoringFromFuture,
+            "input claims to be saltybox, but not a version we support",
+        ));
+    };
+
+    let mut encoded = String::with_capacity(text.len() - body_start);
+    for ch in text[body_start..].chars() {
+        if is_base64url_char(ch) {
+            encoded.push(ch);
+        } else if ch.is_whitespace() || ch == '>' {
+            continue;
+        } else {
+            break;
+        }
+    }
+
+    let body = URL_SAFE_NO_PAD.decode(&encoded).map_err(|e| {
+        SaltyboxError::with_kind_and_source(
+            ErrorCategory::User,
+            ErrorKind::ArmoringDecode,
+            format!("base64 decoding failed: {}", e),
+            e,
+        )
+    })?;
+    Ok((version, body))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,6 +505,191 @@ mod tests {
         assert!(!armored.contains('\t'));
     }
 
+    #[test]
+    fn test_unwrap_version_detects_stream1() {
+        let bytes = b"chunked payload";
+        let armored = wrap_version(bytes, Version::Stream1);
+        let (version, body) = unwrap_version(&armored).unwrap();
+        assert_eq!(version, Version::Stream1);
+        assert_eq!(body, bytes);
+
+        // Plain `unwrap` only accepts the v1 format.
+        let err = unwrap(&armored).expect_err("stream1 should be rejected by plain unwrap");
+        assert_eq!(err.kind, Some(ErrorKind::ArmoringFromFuture));
+    }
+
+    #[test]
+    fn test_unwrap_version_detects_pk1() {
+        let bytes = b"sealed box payload";
+        let armored = wrap_version(bytes, Version::Pk1);
+        let (version, body) = unwrap_version(&armored).unwrap();
+        assert_eq!(version, Version::Pk1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_unwrap_version_detects_params1() {
+        let bytes = b"tunable cost payload";
+        let armored = wrap_version(bytes, Version::Params1);
+        let (version, body) = unwrap_version(&armored).unwrap();
+        assert_eq!(version, Version::Params1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_unwrap_version_detects_kdf1() {
+        let bytes = b"pluggable kdf payload";
+        let armored = wrap_version(bytes, Version::Kdf1);
+        let (version, body) = unwrap_version(&armored).unwrap();
+        assert_eq!(version, Version::Kdf1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_unwrap_version_detects_multi_recipient1() {
+        let bytes = b"multi-recipient wrapped payload";
+        let armored = wrap_version(bytes, Version::MultiRecipient1);
+        let (version, body) = unwrap_version(&armored).unwrap();
+        assert_eq!(version, Version::MultiRecipient1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_crc24_known_vector() {
+        // Standard CRC-24/OPENPGP check value for ASCII "123456789".
+        assert_eq!(crc24(b"123456789"), 0x21CF02);
+    }
+
+    #[test]
+    fn test_armor2_roundtrip() {
+        let bytes = b"armor me please, this is some test ciphertext bytes";
+        let armored = wrap_armor2(bytes);
+        assert!(armored.starts_with(ARMOR2_MAGIC));
+
+        let (version, body) = unwrap_version(&armored).unwrap();
+        assert_eq!(version, Version::V1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_armor2_lines_are_wrapped() {
+        let bytes = vec![0x42u8; 1000];
+        let armored = wrap_armor2(&bytes);
+        for line in armored.lines().skip(1) {
+            if let Some(line) = line.strip_prefix('=') {
+                assert!(line.len() <= ARMOR2_LINE_WIDTH);
+            } else {
+                assert!(line.len() <= ARMOR2_LINE_WIDTH);
+            }
+        }
+    }
+
+    #[test]
+    fn test_armor2_tolerates_extra_whitespace() {
+        let bytes = b"some data";
+        let armored = wrap_armor2(bytes);
+        let noisy = format!("  {}  \n\n", armored.replace('\n', "\n  \n"));
+
+        let (version, body) = unwrap_version(&noisy).unwrap();
+        assert_eq!(version, Version::V1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_armor2_detects_corruption() {
+        let bytes = b"some important data";
+        let armored = wrap_armor2(bytes);
+        // Flip the first character of the base64 body (not the checksum line).
+        let idx = armored.find('\n').unwrap() + 1;
+        let mut chars: Vec<char> = armored.chars().collect();
+        chars[idx] = if chars[idx] == 'A' { 'B' } else { 'A' };
+        let corrupted: String = chars.into_iter().collect();
+
+        let err = unwrap_version(&corrupted).expect_err("corrupted armor should fail checksum");
+        assert_eq!(err.kind, Some(ErrorKind::ArmoringChecksumMismatch));
+    }
+
+    #[test]
+    fn test_armor2_missing_checksum_line() {
+        let err = unwrap_version("saltybox2:\nQUJD\n").expect_err("missing checksum should fail");
+        assert_eq!(err.kind, Some(ErrorKind::ArmoringInvalid));
+    }
+
+    #[test]
+    fn test_unwrap_tolerant_recovers_from_surrounding_prose() {
+        let bytes = b"test";
+        let armored = wrap(bytes);
+        let text = format!("hey, here's that file I mentioned:\n{}\nlet me know!", armored);
+
+        let (version, body) = unwrap_tolerant(&text).unwrap();
+        assert_eq!(version, Version::V1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_unwrap_tolerant_recovers_from_quoted_email() {
+        let bytes = b"quoted reply test data";
+        let armored = wrap(bytes);
+        // Simulate a mail client wrapping and quote-prefixing the token.
+        let mid = armored.len() / 2;
+        let text = format!("> On Tue, she wrote:\n> {}\n> {}\n", &armored[..mid], &armored[mid..]);
+
+        let (version, body) = unwrap_tolerant(&text).unwrap();
+        assert_eq!(version, Version::V1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_unwrap_tolerant_stream1() {
+        let bytes = b"streamed payload";
+        let armored = wrap_version(bytes, Version::Stream1);
+        let text = format!("logline: foo=bar {}\n", armored);
+
+        let (version, body) = unwrap_tolerant(&text).unwrap();
+        assert_eq!(version, Version::Stream1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_unwrap_tolerant_multi1() {
+        let bytes = b"multi recipient payload";
+        let armored = wrap_version(bytes, Version::MultiRecipient1);
+        let text = format!("here's the shared blob:\n{}\nthanks!", armored);
+
+        let (version, body) = unwrap_tolerant(&text).unwrap();
+        assert_eq!(version, Version::MultiRecipient1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_unwrap_tolerant_armor2_quoted() {
+        let bytes = b"pgp style quoted payload";
+        let armored = wrap_armor2(bytes);
+        let quoted: String = armored
+            .lines()
+            .map(|line| format!("> {}\n", line))
+            .collect();
+        let text = format!("Forwarding this along:\n{}", quoted);
+
+        let (version, body) = unwrap_tolerant(&text).unwrap();
+        assert_eq!(version, Version::V1);
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn test_unwrap_tolerant_rejects_unknown_version() {
+        let err = unwrap_tolerant("here's the blob: saltybox999:AAEC")
+            .expect_err("unsupported version should be rejected");
+        assert_eq!(err.kind, Some(ErrorKind::ArmoringFromFuture));
+    }
+
+    #[test]
+    fn test_unwrap_tolerant_no_marker_found() {
+        let err = unwrap_tolerant("this text has no saltybox data in it")
+            .expect_err("missing marker should be rejected");
+        assert_eq!(err.kind, Some(ErrorKind::ArmoringInvalid));
+    }
+
     #[test]
     fn test_url_safe() {
         let bytes = vec![0xFFu8; 100]; // Bytes that might encode to + or / in standard base64
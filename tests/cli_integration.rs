@@ -40,6 +40,33 @@ fn run_saltybox_with_passphrase(
     child.wait_with_output()
 }
 
+/// Run saltybox with passphrase from stdin and `SALTYBOX_CONFIG_DIR` pointed
+/// at `config_dir`, for tests exercising `init`/`--profile` without touching
+/// the real user config directory.
+fn run_saltybox_with_profile_dir(
+    args: &[&str],
+    passphrase: &str,
+    config_dir: &std::path::Path,
+) -> Result<std::process::Output, std::io::Error> {
+    let mut child = Command::new(saltybox_bin())
+        .arg("--passphrase-stdin")
+        .args(args)
+        .env("SALTYBOX_CONFIG_DIR", config_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    {
+        let stdin = child.stdin.as_mut().expect("failed to open stdin");
+        // Ignore BrokenPipe errors - the command may exit before reading stdin
+        // if it encounters an error (e.g., file not found)
+        let _ = stdin.write_all(passphrase.as_bytes());
+    }
+
+    child.wait_with_output()
+}
+
 /// Get path to testdata directory
 fn testdata_path(filename: &str) -> PathBuf {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -288,6 +315,105 @@ fn test_empty_file_roundtrip() {
     assert_eq!(content, b"");
 }
 
+#[test]
+fn test_init_profile_encrypt_decrypt_roundtrip() {
+    let config_dir = TempDir::new().unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let plaintext = temp_dir.path().join("plaintext.txt");
+    let encrypted = temp_dir.path().join("encrypted.txt.salty");
+    let decrypted = temp_dir.path().join("decrypted.txt");
+
+    fs::write(&plaintext, "Profile-encrypted content").unwrap();
+
+    let result =
+        run_saltybox_with_profile_dir(&["init", "work", "--allow-weak-passphrase"], "test", config_dir.path()).unwrap();
+    assert!(
+        result.status.success(),
+        "init failed: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    let result = run_saltybox_with_profile_dir(
+        &[
+            "encrypt",
+            "-i",
+            plaintext.to_str().unwrap(),
+            "-o",
+            encrypted.to_str().unwrap(),
+            "--profile",
+            "work",
+        ],
+        "test",
+        config_dir.path(),
+    )
+    .unwrap();
+    assert!(
+        result.status.success(),
+        "encrypt --profile failed: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    let result = run_saltybox_with_profile_dir(
+        &[
+            "decrypt",
+            "-i",
+            encrypted.to_str().unwrap(),
+            "-o",
+            decrypted.to_str().unwrap(),
+            "--profile",
+            "work",
+        ],
+        "test",
+        config_dir.path(),
+    )
+    .unwrap();
+    assert!(
+        result.status.success(),
+        "decrypt --profile failed: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    let original = fs::read_to_string(&plaintext).unwrap();
+    let decrypted_content = fs::read_to_string(&decrypted).unwrap();
+    assert_eq!(original, decrypted_content);
+}
+
+#[test]
+fn test_encrypt_profile_rejects_wrong_passphrase() {
+    let config_dir = TempDir::new().unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let plaintext = temp_dir.path().join("plaintext.txt");
+    let encrypted = temp_dir.path().join("encrypted.txt.salty");
+
+    fs::write(&plaintext, "content").unwrap();
+
+    let result = run_saltybox_with_profile_dir(
+        &["init", "work", "--allow-weak-passphrase"],
+        "correct_password",
+        config_dir.path(),
+    )
+    .unwrap();
+    assert!(result.status.success());
+
+    let result = run_saltybox_with_profile_dir(
+        &[
+            "encrypt",
+            "-i",
+            plaintext.to_str().unwrap(),
+            "-o",
+            encrypted.to_str().unwrap(),
+            "--profile",
+            "work",
+        ],
+        "wrong_password",
+        config_dir.path(),
+    )
+    .unwrap();
+
+    assert!(!result.status.success());
+    assert!(!encrypted.exists());
+}
+
 #[test]
 fn test_large_file_roundtrip() {
     let temp_dir = TempDir::new().unwrap();
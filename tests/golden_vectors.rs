@@ -12,6 +12,14 @@ struct GoldenVector {
     nonce: String,
     salt: String,
     comment: String,
+    /// Which KDF this vector was generated with: `"scrypt"` or `"argon2id"`.
+    /// Defaults to `"scrypt"` so existing vector files don't need updating.
+    #[serde(default = "default_kdf")]
+    kdf: String,
+}
+
+fn default_kdf() -> String {
+    "scrypt".to_string()
 }
 
 fn load_golden_vectors() -> Result<Vec<GoldenVector>> {
@@ -91,13 +99,23 @@ fn run_golden_vector_tests(indices: Option<&[usize]>) {
             continue;
         }
 
+        let salt: [u8; 8] = salt.try_into().unwrap();
+        let nonce: [u8; 24] = nonce.try_into().unwrap();
+        let is_argon2id = vector.kdf == "argon2id";
+
         // Test deterministic encryption produces exact ciphertext
-        let encrypted = match saltybox::secretcrypt::encrypt_deterministic(
-            &passphrase,
-            &expected_plaintext,
-            &salt.try_into().unwrap(),
-            &nonce.try_into().unwrap(),
-        ) {
+        let encrypted = if is_argon2id {
+            saltybox::secretcrypt::encrypt_deterministic_with_kdf(
+                &passphrase,
+                &expected_plaintext,
+                saltybox::secretcrypt::Kdf::ARGON2ID_DEFAULT,
+                &salt,
+                &nonce,
+            )
+        } else {
+            saltybox::secretcrypt::encrypt_deterministic(&passphrase, &expected_plaintext, &salt, &nonce)
+        };
+        let encrypted = match encrypted {
             Ok(data) => data,
             Err(e) => {
                 eprintln!("Vector {}: FAILED to encrypt - {}", i, e);
@@ -107,7 +125,11 @@ fn run_golden_vector_tests(indices: Option<&[usize]>) {
             }
         };
 
-        let wrapped = saltybox::varmor::wrap(&encrypted);
+        let wrapped = if is_argon2id {
+            saltybox::varmor::wrap_version(&encrypted, saltybox::varmor::Version::Kdf1)
+        } else {
+            saltybox::varmor::wrap(&encrypted)
+        };
 
         if wrapped != vector.ciphertext {
             eprintln!("Vector {}: FAILED - ciphertext mismatch", i);
@@ -119,7 +141,12 @@ fn run_golden_vector_tests(indices: Option<&[usize]>) {
         }
 
         // Also test decryption works (round-trip validation)
-        let unwrapped = match saltybox::varmor::unwrap(&vector.ciphertext) {
+        let unwrapped = if is_argon2id {
+            saltybox::varmor::unwrap_version(&vector.ciphertext).map(|(_, body)| body)
+        } else {
+            saltybox::varmor::unwrap(&vector.ciphertext)
+        };
+        let unwrapped = match unwrapped {
             Ok(data) => data,
             Err(e) => {
                 eprintln!("Vector {}: FAILED to unwrap - {}", i, e);
@@ -129,7 +156,12 @@ fn run_golden_vector_tests(indices: Option<&[usize]>) {
             }
         };
 
-        let decrypted = match saltybox::secretcrypt::decrypt(&passphrase, &unwrapped) {
+        let decrypted = if is_argon2id {
+            saltybox::secretcrypt::decrypt_with_kdf(&passphrase, &unwrapped)
+        } else {
+            saltybox::secretcrypt::decrypt(&passphrase, &unwrapped)
+        };
+        let decrypted = match decrypted {
             Ok(data) => data,
             Err(e) => {
                 eprintln!("Vector {}: FAILED to decrypt - {}", i, e);